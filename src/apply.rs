@@ -0,0 +1,389 @@
+use std::io::{BufRead, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+
+use crate::bundle::{self, BundleFile};
+use crate::cli::{self, PathMode};
+use crate::paths::{PathDisplay, ROOT_PLACEHOLDER};
+
+struct Change {
+    file: BundleFile,
+    destination: PathBuf,
+    display: String,
+    existing: Option<Vec<u8>>,
+}
+
+pub fn run(args: cli::ApplyArgs) -> Result<()> {
+    let from_stdin = args.response.as_os_str() == "-";
+    let response = if from_stdin {
+        let mut response = String::new();
+        std::io::stdin()
+            .read_to_string(&mut response)
+            .context("Cannot read response from stdin")?;
+        response
+    } else {
+        std::fs::read_to_string(&args.response)
+            .with_context(|| format!("Cannot read response \"{}\".", args.response.display()))?
+    };
+
+    let files = extract_file_blocks(&response)?;
+    if files.is_empty() {
+        bail!("No file blocks found in the response.");
+    }
+
+    let paths = PathDisplay::new(PathMode::Relative)?;
+    let mut changes: Vec<Change> = Vec::new();
+    for file in files {
+        let destination = resolve_destination(&paths, &file.path)?;
+        let existing = if destination.is_file() {
+            Some(
+                std::fs::read(&destination)
+                    .with_context(|| format!("Cannot read \"{}\".", destination.display()))?,
+            )
+        } else {
+            None
+        };
+
+        changes.retain(|change| change.destination != destination);
+        changes.push(Change {
+            display: paths.display(&destination),
+            file,
+            destination,
+            existing,
+        });
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    let mut pending = Vec::new();
+    for change in changes {
+        if change.existing.as_deref() == Some(change.file.content.as_slice()) {
+            writeln!(stdout, "unchanged {}", change.display)?;
+            continue;
+        }
+
+        write_diff(&mut stdout, &change)?;
+        pending.push(change);
+    }
+    stdout.flush()?;
+    drop(stdout);
+
+    if pending.is_empty() {
+        println!("Nothing to apply.");
+        return Ok(());
+    }
+
+    if !args.yes && !confirm(pending.len(), from_stdin)? {
+        println!("Aborted; no files were changed.");
+        return Ok(());
+    }
+
+    if args.stash {
+        stash_backup(paths.root())?;
+    }
+
+    for change in &pending {
+        if let Some(parent) = change.destination.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Cannot create directory \"{}\".", parent.display()))?;
+        }
+
+        std::fs::write(&change.destination, &change.file.content)
+            .with_context(|| format!("Cannot write \"{}\".", change.destination.display()))?;
+
+        let verb = if change.existing.is_some() {
+            "updated"
+        } else {
+            "created"
+        };
+        println!("{verb} {}", change.display);
+    }
+
+    Ok(())
+}
+
+pub fn extract_file_blocks(response: &str) -> Result<Vec<BundleFile>> {
+    if let Some(document) = xml_document(response) {
+        return bundle::parse_bundle(document);
+    }
+
+    if response.contains("<file>") && response.contains("<path>") {
+        return bundle::parse_bundle(&wrap_xml_fragments(response));
+    }
+
+    // Text bundles may use `--header-style comment` or `--compact-headers`,
+    // so look for any `File i/n:` header the bundle parser recognises.
+    if response.contains("File ")
+        && let Ok(files) = bundle::parse_bundle(response)
+        && !files.is_empty()
+    {
+        return Ok(files);
+    }
+
+    Ok(markdown_blocks(response))
+}
+
+fn xml_document(response: &str) -> Option<&str> {
+    let start = response
+        .find("<?xml")
+        .or_else(|| response.find("<concatenation"))?;
+    let end = response[start..].rfind("</concatenation>")? + start;
+
+    Some(&response[start..end + "</concatenation>".len()])
+}
+
+fn wrap_xml_fragments(response: &str) -> String {
+    let mut document = String::from("<concatenation><fileContents>");
+    let mut rest = response;
+
+    while let Some(start) = rest.find("<file>") {
+        let Some(end) = rest[start..].find("</file>") else {
+            break;
+        };
+        let end = start + end + "</file>".len();
+
+        document.push_str(&rest[start..end]);
+        rest = &rest[end..];
+    }
+
+    document.push_str("</fileContents></concatenation>");
+    document
+}
+
+fn markdown_blocks(response: &str) -> Vec<BundleFile> {
+    let lines: Vec<&str> = response.split_inclusive('\n').collect();
+    let mut files = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = lines[index].trim_end();
+        let Some(fence) = opening_fence(line) else {
+            index += 1;
+            continue;
+        };
+
+        let info = line.trim_start()[fence.len()..].trim();
+        let Some(close) =
+            (index + 1..lines.len()).find(|&line| is_closing_fence(lines[line], fence))
+        else {
+            break;
+        };
+
+        let path = path_from_info(info).or_else(|| {
+            index
+                .checked_sub(1)
+                .and_then(|previous| {
+                    lines[..=previous]
+                        .iter()
+                        .rev()
+                        .find(|line| !line.trim().is_empty())
+                })
+                .and_then(|line| path_from_heading(line))
+        });
+
+        if let Some(path) = path {
+            files.push(BundleFile {
+                path,
+                content: lines[index + 1..close].concat().into_bytes(),
+                is_binary: false,
+                part: None,
+            });
+        }
+
+        index = close + 1;
+    }
+
+    files
+}
+
+fn opening_fence(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    ["````", "```", "~~~"]
+        .into_iter()
+        .find(|fence| trimmed.starts_with(fence))
+        .map(|fence| {
+            let marker = fence.chars().next().unwrap_or('`');
+            let length = trimmed.chars().take_while(|&c| c == marker).count();
+            &trimmed[..length]
+        })
+}
+
+fn is_closing_fence(line: &str, fence: &str) -> bool {
+    let candidate = line.trim();
+    candidate.starts_with(fence) && candidate.chars().all(|c| fence.starts_with(c))
+}
+
+fn path_from_info(info: &str) -> Option<String> {
+    for token in info.split_whitespace() {
+        for key in ["path=", "file=", "filename="] {
+            if let Some(value) = token.strip_prefix(key) {
+                return Some(value.trim_matches(['"', '\'']).to_string());
+            }
+        }
+    }
+
+    info.split_whitespace()
+        .find(|token| looks_like_path(token))
+        .map(str::to_string)
+}
+
+fn path_from_heading(line: &str) -> Option<String> {
+    let mut candidate = line
+        .trim()
+        .trim_start_matches('#')
+        .trim()
+        .trim_matches('*')
+        .trim();
+
+    for prefix in ["File:", "file:", "Path:", "path:"] {
+        if let Some(rest) = candidate.strip_prefix(prefix) {
+            candidate = rest.trim();
+        }
+    }
+
+    let candidate = candidate
+        .trim_end_matches(':')
+        .trim_matches(['`', '*'])
+        .trim();
+    looks_like_path(candidate).then(|| candidate.to_string())
+}
+
+fn looks_like_path(candidate: &str) -> bool {
+    !candidate.is_empty()
+        && !candidate.contains(char::is_whitespace)
+        && (candidate.contains('/') || candidate.contains('.'))
+}
+
+fn resolve_destination(paths: &PathDisplay, bundle_path: &str) -> Result<PathBuf> {
+    let relative = bundle_path
+        .strip_prefix(ROOT_PLACEHOLDER)
+        .and_then(|rest| rest.strip_prefix('/'));
+
+    let destination = match relative {
+        Some(rest) => paths.absolute(&paths.root().join(rest)),
+        None => paths.absolute(Path::new(bundle_path)),
+    };
+
+    if !resolve_existing_ancestor(&destination).is_some_and(|path| path.starts_with(paths.root())) {
+        bail!(
+            "Refusing to apply \"{bundle_path}\": it lies outside the project root \"{}\".",
+            paths.root().display()
+        );
+    }
+
+    if destination.is_dir() {
+        bail!("Refusing to apply \"{bundle_path}\": it is a directory.");
+    }
+
+    Ok(destination)
+}
+
+// A file that does not exist yet is checked through its nearest existing
+// ancestor, so a symlinked directory cannot lead outside the root. Dangling
+// symlinks point nowhere that can be checked and resolve to `None`.
+fn resolve_existing_ancestor(path: &Path) -> Option<PathBuf> {
+    let mut missing = Vec::new();
+    let mut current = path;
+
+    loop {
+        if let Ok(resolved) = current.canonicalize() {
+            return Some(
+                missing
+                    .into_iter()
+                    .rev()
+                    .fold(resolved, |path, name| path.join(name)),
+            );
+        }
+        if current.symlink_metadata().is_ok() {
+            return None;
+        }
+
+        missing.push(current.file_name()?);
+        current = current.parent()?;
+    }
+}
+
+fn write_diff(out: &mut dyn Write, change: &Change) -> Result<()> {
+    let old_label = if change.existing.is_some() {
+        format!("a/{}", change.display)
+    } else {
+        "/dev/null".to_string()
+    };
+    let new_label = format!("b/{}", change.display);
+
+    let old_text = change
+        .existing
+        .as_deref()
+        .map(String::from_utf8_lossy)
+        .unwrap_or_default();
+    let new_text = String::from_utf8_lossy(&change.file.content);
+
+    let diff = similar::TextDiff::from_lines(old_text.as_ref(), new_text.as_ref());
+    write!(
+        out,
+        "{}",
+        diff.unified_diff()
+            .context_radius(3)
+            .header(&old_label, &new_label)
+    )?;
+    Ok(())
+}
+
+fn confirm(count: usize, stdin_used: bool) -> Result<bool> {
+    let question = format!(
+        "Apply {count} change{}? [y/N] ",
+        if count == 1 { "" } else { "s" }
+    );
+
+    let answer = if stdin_used || !std::io::stdin().is_terminal() {
+        let Ok(tty) = std::fs::File::open("/dev/tty") else {
+            bail!("Cannot ask for confirmation without a terminal; pass --yes to apply.");
+        };
+        eprint!("{question}");
+        let mut answer = String::new();
+        std::io::BufReader::new(tty).read_line(&mut answer)?;
+        answer
+    } else {
+        eprint!("{question}");
+        let mut answer = String::new();
+        std::io::stdin().lock().read_line(&mut answer)?;
+        answer
+    };
+
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes" | "Yes"))
+}
+
+fn stash_backup(root: &Path) -> Result<()> {
+    let created = Command::new("git")
+        .args(["stash", "create"])
+        .current_dir(root)
+        .output()
+        .context("Failed to run git stash create")?;
+
+    if !created.status.success() {
+        bail!(
+            "git stash create failed: {}",
+            String::from_utf8_lossy(&created.stderr).trim()
+        );
+    }
+
+    let commit = String::from_utf8_lossy(&created.stdout).trim().to_string();
+    if commit.is_empty() {
+        eprintln!("Working tree is clean; no stash created.");
+        return Ok(());
+    }
+
+    let stored = Command::new("git")
+        .args(["stash", "store", "-m", "concat apply backup", &commit])
+        .current_dir(root)
+        .status()
+        .context("Failed to run git stash store")?;
+
+    if !stored.success() {
+        bail!("git stash store failed.");
+    }
+
+    eprintln!("Saved working tree changes to the git stash ({commit}).");
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+use crate::cli::PathMode;
+use crate::config::{OutputFormat, RunConfig};
+use crate::output::{self, FileMetadata};
+use crate::paths::PathDisplay;
+use crate::template::{self, Template};
+use crate::text_detect;
+
+pub const MANIFEST_NAME: &str = "concat-manifest.xml";
+const MANIFEST_TEMPLATE: &str = include_str!("templates/manifest.tmpl");
+
+struct ArchiveEntry<'a> {
+    source: &'a Path,
+    name: String,
+    redacted: Option<Vec<u8>>,
+}
+
+pub fn write_archive<W: Write + Seek>(
+    config: &RunConfig,
+    matched_files: &[PathBuf],
+    metadata: &[FileMetadata],
+    out: W,
+) -> Result<()> {
+    let paths = PathDisplay::new(PathMode::Root)?;
+    let entries = archive_entries(config, &paths, matched_files)?;
+    let manifest = render_manifest(config, &paths, metadata)?;
+
+    match config.format {
+        OutputFormat::Tar => write_tar(out, &entries, &manifest),
+        OutputFormat::Zip => write_zip(out, &entries, &manifest),
+        format => bail!("--format {} is not an archive format.", format.as_str()),
+    }
+}
+
+pub fn write_tar_archive<W: Write>(
+    config: &RunConfig,
+    matched_files: &[PathBuf],
+    metadata: &[FileMetadata],
+    out: W,
+) -> Result<()> {
+    let paths = PathDisplay::new(PathMode::Root)?;
+    let entries = archive_entries(config, &paths, matched_files)?;
+    let manifest = render_manifest(config, &paths, metadata)?;
+
+    write_tar(out, &entries, &manifest)
+}
+
+fn archive_entries<'a>(
+    config: &RunConfig,
+    paths: &PathDisplay,
+    matched_files: &'a [PathBuf],
+) -> Result<Vec<ArchiveEntry<'a>>> {
+    let mut entries = Vec::with_capacity(matched_files.len());
+    for file_path in matched_files {
+        let name = archive_name(paths, file_path)?;
+        if name == MANIFEST_NAME {
+            bail!(
+                "Cannot archive \"{}\": the name is reserved for the manifest.",
+                file_path.display()
+            );
+        }
+
+        entries.push(ArchiveEntry {
+            source: file_path,
+            name,
+            redacted: redact_file(config, file_path),
+        });
+    }
+
+    Ok(entries)
+}
+
+// Text files lose their secrets unless `--no-redact` is given.
+fn redact_file(config: &RunConfig, file_path: &Path) -> Option<Vec<u8>> {
+    let bytes = std::fs::read(file_path).ok()?;
+    if !text_detect::bytes_are_probably_text(&bytes) {
+        return None;
+    }
+
+    output::redact_text(config, file_path, &String::from_utf8_lossy(&bytes)).map(String::into_bytes)
+}
+
+fn archive_name(paths: &PathDisplay, file_path: &Path) -> Result<String> {
+    let absolute = paths.absolute(file_path);
+    let Ok(relative) = absolute.strip_prefix(paths.root()) else {
+        bail!(
+            "Cannot archive \"{}\": it lies outside the project root \"{}\".",
+            file_path.display(),
+            paths.root().display()
+        );
+    };
+
+    let parts: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().into_owned())
+        .collect();
+
+    Ok(parts.join("/"))
+}
+
+fn render_manifest(
+    config: &RunConfig,
+    paths: &PathDisplay,
+    metadata: &[FileMetadata],
+) -> Result<Vec<u8>> {
+    let metadata = output::sort_file_metadata(metadata, config.metadata_sort);
+
+    let mut ctx = template::Context::new();
+    ctx.set("format", config.format.as_str());
+    ctx.set("total", metadata.len());
+    ctx.set("total_tokens", output::total_tokens(&metadata));
+    ctx.set(
+        "metadata",
+        output::metadata_list_context(paths, &metadata, metadata.len()),
+    );
+
+    let mut manifest = Vec::new();
+    Template::parse("manifest", "xml", MANIFEST_TEMPLATE)?.render_header(&ctx, &mut manifest)?;
+
+    Ok(manifest)
+}
+
+fn write_tar<W: Write>(out: W, entries: &[ArchiveEntry], manifest: &[u8]) -> Result<()> {
+    let mut builder = tar::Builder::new(out);
+
+    for entry in entries {
+        let added = match &entry.redacted {
+            Some(bytes) => std::fs::metadata(entry.source).and_then(|metadata| {
+                let mut header = tar::Header::new_gnu();
+                header.set_metadata(&metadata);
+                header.set_size(bytes.len() as u64);
+                builder.append_data(&mut header, &entry.name, bytes.as_slice())
+            }),
+            None => builder.append_path_with_name(entry.source, &entry.name),
+        };
+        added.with_context(|| format!("Cannot add \"{}\" to archive.", entry.source.display()))?;
+    }
+
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0),
+    );
+    builder.append_data(&mut header, MANIFEST_NAME, manifest)?;
+
+    builder.into_inner()?.flush()?;
+    Ok(())
+}
+
+fn write_zip<W: Write + Seek>(out: W, entries: &[ArchiveEntry], manifest: &[u8]) -> Result<()> {
+    let mut writer = zip::ZipWriter::new(out);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .unix_permissions(0o644);
+
+    for entry in entries {
+        let bytes = match &entry.redacted {
+            Some(bytes) => bytes.clone(),
+            None => std::fs::read(entry.source)
+                .with_context(|| format!("Cannot read \"{}\".", entry.source.display()))?,
+        };
+        writer.start_file(entry.name.as_str(), options)?;
+        writer.write_all(&bytes)?;
+    }
+
+    writer.start_file(MANIFEST_NAME, options)?;
+    writer.write_all(manifest)?;
+
+    writer.finish()?.flush()?;
+    Ok(())
+}
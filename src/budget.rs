@@ -0,0 +1,305 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+use crate::cli::Rank;
+use crate::config::RunConfig;
+use crate::output::FileMetadata;
+use crate::paths::PathDisplay;
+use crate::template;
+
+#[derive(Debug)]
+pub struct TokenBudget {
+    pub max_tokens: usize,
+    pub priority: Vec<String>,
+    pub rank: Rank,
+}
+
+impl TokenBudget {
+    pub fn describe(&self) -> String {
+        let rank = match self.rank {
+            Rank::Size => "smallest first",
+            Rank::Distance => "closest to inputs first",
+        };
+
+        if self.priority.is_empty() {
+            format!("{} tokens, {rank}", self.max_tokens)
+        } else {
+            format!(
+                "{} tokens, priority {}, then {rank}",
+                self.max_tokens,
+                self.priority.join(" ")
+            )
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct OmittedFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub tokens: usize,
+}
+
+pub struct BudgetReport {
+    pub used_tokens: usize,
+    pub omitted: Vec<OmittedFile>,
+}
+
+struct Candidate {
+    index: usize,
+    path: PathBuf,
+    metadata: FileMetadata,
+    size: u64,
+    priority: usize,
+    distance: usize,
+    section_tokens: usize,
+}
+
+// A file that does not fit is skipped, but smaller files after it still get their chance.
+pub fn select(
+    config: &RunConfig,
+    budget: &TokenBudget,
+    inputs: &[PathBuf],
+    matched: Vec<PathBuf>,
+    metadata: Vec<FileMetadata>,
+    tree: Option<&str>,
+) -> Result<(Vec<PathBuf>, Vec<FileMetadata>, BudgetReport)> {
+    let priority = build_priority_globs(&budget.priority)?;
+
+    let mut candidates: Vec<Candidate> = matched
+        .into_iter()
+        .zip(metadata)
+        .enumerate()
+        .map(|(index, (path, metadata))| Candidate {
+            index,
+            size: std::fs::metadata(&path).map_or(0, |metadata| metadata.len()),
+            priority: priority_rank(&priority, &config.path_display, &path),
+            distance: distance_to_inputs(inputs, &path),
+            section_tokens: 0,
+            path,
+            metadata,
+        })
+        .collect();
+
+    candidates.sort_by(|a, b| {
+        a.priority
+            .cmp(&b.priority)
+            .then_with(|| match budget.rank {
+                Rank::Size => a.size.cmp(&b.size),
+                Rank::Distance => a.distance.cmp(&b.distance).then(a.size.cmp(&b.size)),
+            })
+            .then_with(|| a.path.cmp(&b.path))
+    });
+
+    let mut report = BudgetReport {
+        used_tokens: 0,
+        omitted: Vec::new(),
+    };
+    let mut included = Vec::new();
+
+    for candidate in candidates {
+        if report.used_tokens + candidate.metadata.tokens <= budget.max_tokens {
+            report.used_tokens += candidate.metadata.tokens;
+            included.push(candidate);
+        } else {
+            report.omitted.push(candidate.omitted());
+        }
+    }
+
+    // Headers, the overview sections and the stubs cost tokens too. Measure
+    // the bundle without any files and what one file adds to it, drop the
+    // lowest-ranked files until the estimate fits, then render to confirm.
+    let total = included.len();
+    for candidate in &mut included {
+        let section = crate::output::render_file_section(
+            config,
+            &candidate.path,
+            &candidate.metadata,
+            candidate.index + 1,
+            total,
+            None,
+        )?;
+        candidate.section_tokens = config.tokenizer.count(&section);
+    }
+
+    let fixed = rendered_tokens(config, tree, &report, &[], &included)?;
+    let share = match included.split_last() {
+        Some((sample, rest)) => {
+            let with_sample =
+                rendered_tokens(config, tree, &report, std::slice::from_ref(sample), rest)?;
+            with_sample.saturating_sub(fixed + sample.section_tokens)
+        }
+        None => 0,
+    };
+    let mut estimate = fixed
+        + included
+            .iter()
+            .map(|candidate| candidate.section_tokens + share)
+            .sum::<usize>();
+
+    loop {
+        while estimate > budget.max_tokens
+            && let Some(candidate) = included.pop()
+        {
+            estimate -= candidate.section_tokens + share;
+            report.used_tokens -= candidate.metadata.tokens;
+            report.omitted.push(candidate.omitted());
+        }
+
+        let (paths, metadata) = in_matched_order(&included);
+        let rendered =
+            crate::output::render_output(config, &paths, &metadata, tree, Some(&report))?;
+        let tokens = config.tokenizer.count(&rendered);
+
+        if tokens <= budget.max_tokens {
+            return Ok((paths, metadata, report));
+        }
+        if included.is_empty() {
+            bail!(
+                "--max-tokens {} is too small to hold the bundle without any files ({tokens} tokens).",
+                budget.max_tokens
+            );
+        }
+
+        // The estimate came in low; drop files by their cost from the real count.
+        estimate = tokens;
+    }
+}
+
+fn rendered_tokens(
+    config: &RunConfig,
+    tree: Option<&str>,
+    report: &BudgetReport,
+    included: &[Candidate],
+    omitted: &[Candidate],
+) -> Result<usize> {
+    let report = BudgetReport {
+        used_tokens: report.used_tokens,
+        omitted: report
+            .omitted
+            .iter()
+            .cloned()
+            .chain(omitted.iter().map(Candidate::omitted))
+            .collect(),
+    };
+
+    let (paths, metadata) = in_matched_order(included);
+    let rendered = crate::output::render_output(config, &paths, &metadata, tree, Some(&report))?;
+    Ok(config.tokenizer.count(&rendered))
+}
+
+impl Candidate {
+    fn omitted(&self) -> OmittedFile {
+        OmittedFile {
+            path: self.path.clone(),
+            size: self.size,
+            tokens: self.metadata.tokens,
+        }
+    }
+}
+
+fn in_matched_order(included: &[Candidate]) -> (Vec<PathBuf>, Vec<FileMetadata>) {
+    let mut ordered: Vec<&Candidate> = included.iter().collect();
+    ordered.sort_by_key(|candidate| candidate.index);
+
+    ordered
+        .into_iter()
+        .map(|candidate| (candidate.path.clone(), candidate.metadata.clone()))
+        .unzip()
+}
+
+fn build_priority_globs(patterns: &[String]) -> Result<Vec<GlobSet>> {
+    patterns
+        .iter()
+        .map(|pattern| {
+            let mut builder = GlobSetBuilder::new();
+            builder.add(Glob::new(pattern)?);
+            Ok(builder.build()?)
+        })
+        .collect()
+}
+
+fn priority_rank(globs: &[GlobSet], paths: &PathDisplay, path: &Path) -> usize {
+    let relative = paths.relative_to_cwd(path);
+    let name = path.file_name().unwrap_or_default();
+
+    globs
+        .iter()
+        .position(|set| set.is_match(&relative) || set.is_match(name) || set.is_match(path))
+        .unwrap_or(globs.len())
+}
+
+fn distance_to_inputs(inputs: &[PathBuf], path: &Path) -> usize {
+    inputs
+        .iter()
+        .filter_map(|input| {
+            if input == path {
+                return Some(0);
+            }
+
+            path.strip_prefix(input)
+                .ok()
+                .map(|rest| rest.components().count())
+        })
+        .min()
+        .unwrap_or(usize::MAX)
+}
+
+pub fn set_context(
+    ctx: &mut template::Context,
+    paths: &PathDisplay,
+    budget: Option<(&TokenBudget, &BudgetReport)>,
+) {
+    ctx.set("budget", budget.is_some());
+    let Some((budget, report)) = budget else {
+        return;
+    };
+
+    ctx.set("budget_max", budget.max_tokens);
+    ctx.set("budget_used", report.used_tokens);
+    ctx.set("budget_omitted", report.omitted.len());
+    ctx.set(
+        "omitted",
+        report
+            .omitted
+            .iter()
+            .enumerate()
+            .map(|(index, file)| {
+                let mut file_ctx = template::Context::new();
+                file_ctx.set("index", index + 1);
+                file_ctx.set("path", paths.display(&file.path));
+                file_ctx.set("size", file.size as usize);
+                file_ctx.set("tokens", file.tokens);
+                file_ctx
+            })
+            .collect::<Vec<_>>(),
+    );
+}
+
+pub fn print_report(
+    config: &RunConfig,
+    budget: &TokenBudget,
+    report: &BudgetReport,
+    included: usize,
+) {
+    eprintln!(
+        "Token budget: {} of {} tokens used by {included} files.",
+        report.used_tokens, budget.max_tokens
+    );
+
+    if report.omitted.is_empty() {
+        return;
+    }
+
+    let dropped: usize = report.omitted.iter().map(|file| file.tokens).sum();
+    eprintln!("Omitted {} files ({dropped} tokens):", report.omitted.len());
+    for file in &report.omitted {
+        eprintln!(
+            "  {} ({} tokens)",
+            config.path_display.display(&file.path),
+            file.tokens
+        );
+    }
+}
@@ -0,0 +1,429 @@
+use anyhow::{Context, Result, bail};
+use base64::Engine as _;
+use roxmltree::{Document, Node};
+
+const COMMENT_MARKERS: &[(&str, &str)] = &[
+    ("<!--", "-->"),
+    ("/*", "*/"),
+    ("//", ""),
+    ("--", ""),
+    ("#", ""),
+    (";", ""),
+    ("%", ""),
+];
+
+pub type PartNumber = (usize, usize);
+
+#[derive(Debug, Clone)]
+pub struct BundleFile {
+    pub path: String,
+    pub content: Vec<u8>,
+    pub is_binary: bool,
+    pub part: Option<PartNumber>,
+}
+
+pub fn parse_bundle(source: &str) -> Result<Vec<BundleFile>> {
+    if is_xml_bundle(source) {
+        parse_xml_bundle(source)
+    } else {
+        parse_text_bundle(source)
+    }
+}
+
+pub fn is_xml_bundle(source: &str) -> bool {
+    let trimmed = source.trim_start_matches('\u{feff}').trim_start();
+    trimmed.starts_with("<?xml") || trimmed.starts_with("<concatenation")
+}
+
+pub fn join_parts(files: Vec<BundleFile>) -> Result<Vec<BundleFile>> {
+    let mut joined: Vec<BundleFile> = Vec::new();
+    let mut chunks: Vec<Vec<BundleFile>> = Vec::new();
+
+    for file in files {
+        match joined
+            .iter()
+            .position(|existing| existing.path == file.path)
+        {
+            Some(position) if file.part.is_some() => chunks[position].push(file),
+            Some(position) => {
+                joined[position] = file;
+                chunks[position].clear();
+            }
+            None => {
+                chunks.push(if file.part.is_some() {
+                    vec![file.clone()]
+                } else {
+                    Vec::new()
+                });
+                joined.push(file);
+            }
+        }
+    }
+
+    for (file, mut pieces) in joined.iter_mut().zip(chunks) {
+        if pieces.is_empty() {
+            continue;
+        }
+
+        pieces.sort_by_key(|piece| piece.part.map(|(number, _)| number));
+        pieces.dedup_by_key(|piece| piece.part);
+
+        let count = pieces[0].part.map(|(_, count)| count).unwrap_or(1);
+        let complete = pieces.len() == count
+            && pieces
+                .iter()
+                .enumerate()
+                .all(|(index, piece)| piece.part == Some((index + 1, count)));
+
+        if !complete {
+            bail!(
+                "\"{}\" is split into {count} parts but only {} were found; pass every part bundle.",
+                file.path,
+                pieces.len()
+            );
+        }
+
+        file.content = pieces.into_iter().flat_map(|piece| piece.content).collect();
+        file.part = None;
+    }
+
+    Ok(joined)
+}
+
+fn parse_xml_bundle(source: &str) -> Result<Vec<BundleFile>> {
+    let doc = Document::parse(source).context("Bundle is not well-formed XML")?;
+    let root = doc.root_element();
+
+    if !root.has_tag_name("concatenation") {
+        bail!(
+            "Expected a <concatenation> root element, found <{}>.",
+            root.tag_name().name()
+        );
+    }
+
+    let Some(contents) = child_element(root, "fileContents") else {
+        bail!("Bundle has no <fileContents> section.");
+    };
+
+    let mut files = Vec::new();
+    for file in contents.children().filter(|node| node.has_tag_name("file")) {
+        let Some(path) = child_element(file, "path").map(node_text) else {
+            bail!("A <file> entry is missing its <path>.");
+        };
+        let Some(content) = child_element(file, "content") else {
+            bail!("File \"{path}\" is missing its <content>.");
+        };
+
+        let part = file.attribute("part").and_then(parse_part);
+        let body = node_text(content);
+        let body = body.strip_prefix('\n').unwrap_or(&body);
+
+        if content.attribute("encoding") == Some("base64") {
+            let encoded: String = body.split_whitespace().collect();
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .with_context(|| format!("File \"{path}\" has invalid base64 content."))?;
+
+            files.push(BundleFile {
+                path,
+                content: bytes,
+                is_binary: true,
+                part,
+            });
+            continue;
+        }
+
+        let text = if content.attribute("lineNumbers") == Some("true") {
+            strip_line_numbers(body)
+        } else {
+            body.to_string()
+        };
+
+        files.push(BundleFile {
+            path,
+            content: text.into_bytes(),
+            is_binary: false,
+            part,
+        });
+    }
+
+    Ok(files)
+}
+
+fn child_element<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn node_text(node: Node) -> String {
+    node.children()
+        .filter(|child| child.is_text())
+        .filter_map(|child| child.text())
+        .collect()
+}
+
+fn strip_line_numbers(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+
+    for line in text.split_inclusive('\n') {
+        let unnumbered = line
+            .trim_start_matches(' ')
+            .trim_start_matches(|c: char| c.is_ascii_digit());
+
+        match unnumbered.strip_prefix(" |") {
+            Some(rest) => stripped.push_str(rest.strip_prefix(' ').unwrap_or(rest)),
+            None => stripped.push_str(line),
+        }
+    }
+
+    stripped
+}
+
+fn parse_text_bundle(source: &str) -> Result<Vec<BundleFile>> {
+    let lines: Vec<&str> = source.split_inclusive('\n').collect();
+    let mut files = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let Some(header) = file_header_at(&lines, index) else {
+            index += 1;
+            continue;
+        };
+
+        let start = index + if header.banner { 2 } else { 1 };
+        let next_header = (start..lines.len()).find(|&line| {
+            file_header_at(&lines, line)
+                .is_some_and(|next| next.total == header.total && next.number > header.number)
+        });
+        let end = next_header.unwrap_or(lines.len());
+
+        if !header.banner {
+            // Compact headers have no EOF marker; the blank line in front of
+            // the next header separates the files.
+            let body_end = if next_header.is_some() { end - 1 } else { end };
+            let body: String = lines[start..body_end.max(start)].concat();
+            files.push(text_bundle_file(&header, body)?);
+            index = end;
+            continue;
+        }
+
+        let Some(eof) = (start..end).find(|&line| is_eof_marker(&lines, line, &header.path)) else {
+            // Unreadable files are written as a header with no body or EOF marker.
+            index = end;
+            continue;
+        };
+
+        let body: String = lines[start..eof - 1].concat();
+        files.push(text_bundle_file(&header, body)?);
+        index = eof + 1;
+    }
+
+    if files.is_empty()
+        && !source.contains("# File Contents (")
+        && !source.contains("# Part Index (")
+    {
+        bail!("Bundle is neither concat XML nor concat text output.");
+    }
+
+    Ok(files)
+}
+
+struct FileHeader {
+    number: usize,
+    total: usize,
+    part: Option<PartNumber>,
+    path: String,
+    banner: bool,
+    numbered: bool,
+}
+
+fn file_header_at(lines: &[&str], index: usize) -> Option<FileHeader> {
+    if index == 0 || index + 1 >= lines.len() {
+        return None;
+    }
+
+    let header = uncomment(line_body(lines[index])).strip_prefix("File ")?;
+    let (position, path) = header.split_once(": ")?;
+    let (position, numbered) = match position.strip_suffix(" (line numbers)") {
+        Some(position) => (position, true),
+        None => (position, false),
+    };
+    let (position, part) = match position.split_once(" (part ") {
+        Some((position, part)) => (position, Some(parse_part(part.strip_suffix(')')?)?)),
+        None => (position, None),
+    };
+    let (current, total) = position.split_once('/')?;
+    let number: usize = current.parse().ok()?;
+    let total: usize = total.parse().ok()?;
+
+    let banner = is_rule(lines[index - 1]) && is_rule(lines[index + 1]);
+    if !banner && !line_body(lines[index - 1]).is_empty() {
+        return None;
+    }
+
+    Some(FileHeader {
+        number,
+        total,
+        part,
+        path: path.to_string(),
+        banner,
+        numbered,
+    })
+}
+
+fn is_eof_marker(lines: &[&str], index: usize, path: &str) -> bool {
+    index > 0
+        && index + 1 < lines.len()
+        && line_body(lines[index - 1]).is_empty()
+        && uncomment(line_body(lines[index])).strip_prefix("EOF: ") == Some(path)
+        && is_rule(lines[index + 1])
+}
+
+fn is_rule(line: &str) -> bool {
+    let rule = uncomment(line_body(line));
+    let mut chars = rule.chars();
+
+    chars.next().is_some_and(|first| {
+        !first.is_alphanumeric()
+            && !first.is_whitespace()
+            && rule.chars().count() >= 3
+            && chars.all(|c| c == first)
+    })
+}
+
+fn uncomment(line: &str) -> &str {
+    let line = line.trim();
+
+    COMMENT_MARKERS
+        .iter()
+        .find_map(|(open, close)| line.strip_prefix(open)?.strip_suffix(close))
+        .map_or(line, str::trim)
+}
+
+fn text_bundle_file(header: &FileHeader, body: String) -> Result<BundleFile> {
+    let path = header.path.clone();
+    let part = header.part;
+
+    if let Some(encoded) = body.strip_prefix("[BINARY FILE: base64]\n") {
+        let encoded: String = encoded.split_whitespace().collect();
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .with_context(|| format!("File \"{path}\" has invalid base64 content."))?;
+
+        return Ok(BundleFile {
+            path,
+            content: bytes,
+            is_binary: true,
+            part,
+        });
+    }
+
+    let text = if header.numbered {
+        strip_line_numbers(&body)
+    } else {
+        body
+    };
+
+    Ok(BundleFile {
+        path,
+        content: text.into_bytes(),
+        is_binary: false,
+        part,
+    })
+}
+
+fn parse_part(value: &str) -> Option<PartNumber> {
+    let (number, count) = value.split_once('/')?;
+    Some((number.parse().ok()?, count.parse().ok()?))
+}
+
+fn line_body(line: &str) -> &str {
+    line.trim_end_matches(['\n', '\r'])
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BundleMetadata {
+    pub path: String,
+    pub lines: Option<usize>,
+    pub characters: Option<usize>,
+    pub tokens: Option<usize>,
+}
+
+pub fn parse_bundle_metadata(source: &str) -> Result<Vec<BundleMetadata>> {
+    let trimmed = source.trim_start_matches('\u{feff}').trim_start();
+
+    if trimmed.starts_with("<?xml") || trimmed.starts_with("<concatenation") {
+        parse_xml_metadata(source)
+    } else {
+        Ok(parse_text_metadata(source))
+    }
+}
+
+fn parse_xml_metadata(source: &str) -> Result<Vec<BundleMetadata>> {
+    let doc = Document::parse(source).context("Bundle is not well-formed XML")?;
+    let Some(section) = child_element(doc.root_element(), "fileMetadata") else {
+        return Ok(Vec::new());
+    };
+
+    let count = |file: Node, name: &str| {
+        child_element(file, name).and_then(|node| node_text(node).trim().parse().ok())
+    };
+
+    Ok(section
+        .children()
+        .filter(|node| node.has_tag_name("file"))
+        .filter_map(|file| {
+            Some(BundleMetadata {
+                path: child_element(file, "path").map(node_text)?,
+                lines: count(file, "lines"),
+                characters: count(file, "characters"),
+                tokens: count(file, "tokens"),
+            })
+        })
+        .collect())
+}
+
+fn parse_text_metadata(source: &str) -> Vec<BundleMetadata> {
+    let lines: Vec<&str> = source.lines().collect();
+    let Some(header) = lines
+        .iter()
+        .position(|line| line.starts_with("# File Metadata ("))
+    else {
+        return Vec::new();
+    };
+
+    lines[header + 1..]
+        .iter()
+        .skip_while(|line| is_rule(line))
+        .take_while(|line| !line.is_empty() && !is_rule(line))
+        .filter_map(|line| parse_text_metadata_line(line))
+        .collect()
+}
+
+fn parse_text_metadata_line(line: &str) -> Option<BundleMetadata> {
+    let (index, rest) = line.split_once(": ")?;
+    index.parse::<usize>().ok()?;
+
+    let (path, details) = rest.rsplit_once(" (")?;
+    let path = path.strip_suffix(" [binary]").unwrap_or(path);
+
+    let mut entry = BundleMetadata {
+        path: path.to_string(),
+        ..BundleMetadata::default()
+    };
+
+    for field in details.strip_suffix(')')?.split(", ") {
+        let Some((key, value)) = field.split_once(": ") else {
+            continue;
+        };
+
+        let value = value.parse().ok();
+        match key {
+            "lines" => entry.lines = value,
+            "chars" => entry.characters = value,
+            "tokens" => entry.tokens = value,
+            _ => {}
+        }
+    }
+
+    Some(entry)
+}
@@ -58,9 +58,7 @@ pub fn delete_concat_outputs_in_cwd(verbose: bool) -> Result<()> {
             continue;
         }
 
-        if let Some(file_name) = path.file_name().and_then(|name| name.to_str())
-            && file_name.starts_with("_concat-")
-        {
+        if is_concat_output_name(&path) {
             let _ = std::fs::remove_file(&path);
         }
     }
@@ -68,6 +66,28 @@ pub fn delete_concat_outputs_in_cwd(verbose: bool) -> Result<()> {
     Ok(())
 }
 
+pub fn is_concat_output_in_cwd(path: &Path) -> bool {
+    if !is_concat_output_name(path) {
+        return false;
+    }
+
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    match (parent.canonicalize(), std::env::current_dir()) {
+        (Ok(parent), Ok(cwd)) => parent == cwd.canonicalize().unwrap_or(cwd),
+        _ => false,
+    }
+}
+
+fn is_concat_output_name(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("_concat-"))
+}
+
 pub fn remove_existing_output_file(output_path: &Path, verbose: bool) -> Result<()> {
     if output_path.exists() {
         if verbose {
@@ -14,6 +14,11 @@ pub struct Cli {
 #[derive(Subcommand, Debug)]
 pub enum Command {
     Clean(CleanArgs),
+    Validate(ValidateArgs),
+    Unpack(UnpackArgs),
+    Apply(ApplyArgs),
+    Diff(DiffArgs),
+    Rerun(RerunArgs),
 }
 
 #[derive(Args, Debug, Default)]
@@ -24,6 +29,9 @@ pub struct RunArgs {
     #[arg(short = 'y', long = "clipboard")]
     pub clipboard: bool,
 
+    #[arg(long = "stdout")]
+    pub stdout: bool,
+
     #[arg(short = 'r', long = "recursive")]
     pub recursive: bool,
 
@@ -33,6 +41,9 @@ pub struct RunArgs {
     #[arg(short = 't', long = "text")]
     pub text: bool,
 
+    #[arg(long = "format", value_enum)]
+    pub format: Option<Format>,
+
     #[arg(short = 'x', long = "ext")]
     pub exts: Vec<String>,
 
@@ -72,6 +83,122 @@ pub struct RunArgs {
     #[arg(long = "metadata-sort", value_enum, default_value_t = MetadataSort::Lines)]
     pub metadata_sort: MetadataSort,
 
+    #[arg(long = "tokenizer", value_enum, default_value_t = Tokenizer::Cl100k)]
+    pub tokenizer: Tokenizer,
+
+    #[arg(long = "xml-schema", value_enum, default_value_t = XmlSchema::V1)]
+    pub xml_schema: XmlSchema,
+
+    #[arg(long = "paths", value_enum, default_value_t = PathMode::Relative)]
+    pub paths: PathMode,
+
+    #[arg(long = "line-numbers")]
+    pub line_numbers: bool,
+
+    #[arg(long = "template", value_name = "xml|text|FILE")]
+    pub template: Option<String>,
+
+    #[arg(
+        long = "split-size",
+        value_name = "SIZE",
+        conflicts_with = "split_tokens"
+    )]
+    pub split_size: Option<String>,
+
+    #[arg(long = "split-tokens", value_name = "N")]
+    pub split_tokens: Option<usize>,
+
+    #[arg(
+        long = "file-attrs",
+        value_enum,
+        value_delimiter = ',',
+        value_name = "ATTRS"
+    )]
+    pub file_attrs: Vec<FileAttr>,
+
+    #[arg(long = "manifest")]
+    pub manifest: bool,
+
+    #[arg(long = "prompt", value_name = "TEXT", conflicts_with = "prompt_file")]
+    pub prompt: Option<String>,
+
+    #[arg(long = "prompt-file", value_name = "FILE")]
+    pub prompt_file: Option<std::path::PathBuf>,
+
+    #[arg(long = "prompt-position", value_enum)]
+    pub prompt_position: Option<PromptPosition>,
+
+    #[arg(long = "notebook-outputs")]
+    pub notebook_outputs: bool,
+
+    #[arg(long = "no-redact")]
+    pub no_redact: bool,
+
+    #[arg(long = "redact-rules", value_name = "FILE")]
+    pub redact_rules: Vec<std::path::PathBuf>,
+
+    #[arg(long = "fail-on-secret")]
+    pub fail_on_secret: bool,
+
+    #[arg(long = "strip-comments")]
+    pub strip_comments: bool,
+
+    #[arg(long = "keep-doc-comments", requires = "strip_comments")]
+    pub keep_doc_comments: bool,
+
+    #[arg(
+        long = "minify-whitespace",
+        value_name = "LEVEL",
+        value_parser = clap::value_parser!(u8).range(1..=3)
+    )]
+    pub minify_whitespace: Option<u8>,
+
+    #[arg(long = "outline")]
+    pub outline: bool,
+
+    #[arg(long = "full", value_name = "GLOB", requires = "outline")]
+    pub full: Vec<String>,
+
+    #[arg(
+        long = "truncate-lines",
+        value_name = "N",
+        conflicts_with = "truncate_bytes"
+    )]
+    pub truncate_lines: Option<usize>,
+
+    #[arg(long = "truncate-bytes", value_name = "N")]
+    pub truncate_bytes: Option<usize>,
+
+    #[arg(long = "truncate-strategy", value_enum)]
+    pub truncate_strategy: Option<TruncateStrategy>,
+
+    #[arg(long = "truncate-glob", value_name = "GLOB=STRATEGY[:N]")]
+    pub truncate_glob: Vec<String>,
+
+    #[arg(long = "max-tokens", value_name = "N")]
+    pub max_tokens: Option<usize>,
+
+    #[arg(long = "priority", value_name = "GLOB", requires = "max_tokens")]
+    pub priority: Vec<String>,
+
+    #[arg(long = "rank", value_enum, requires = "max_tokens")]
+    pub rank: Option<Rank>,
+
+    #[arg(long = "compress", value_enum)]
+    pub compress: Option<Compress>,
+
+    #[arg(long = "header-style", value_enum)]
+    pub header_style: Option<HeaderStyle>,
+
+    #[arg(long = "banner-width", value_name = "N")]
+    pub banner_width: Option<usize>,
+
+    #[arg(long = "banner-chars", value_name = "CHARS")]
+    pub banner_chars: Option<String>,
+
+    #[arg(long = "compact-headers")]
+    pub compact_headers: bool,
+
     #[arg(short = 'C', long = "no-clean-concat")]
     pub no_clean_concat: bool,
 
@@ -79,17 +206,93 @@ pub struct RunArgs {
     pub inputs: Vec<String>,
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
 pub enum MetadataSort {
+    #[default]
     Lines,
     Characters,
+    Tokens,
     Natural,
 }
 
-impl Default for MetadataSort {
-    fn default() -> Self {
-        Self::Lines
-    }
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum Rank {
+    #[default]
+    Size,
+    Distance,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum PromptPosition {
+    #[default]
+    Before,
+    After,
+    Both,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum TruncateStrategy {
+    Head,
+    Tail,
+    #[default]
+    #[value(name = "head+tail")]
+    HeadTail,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum Tokenizer {
+    #[default]
+    Cl100k,
+    O200k,
+    Approx,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum Format {
+    Xml,
+    Text,
+    Html,
+    Tar,
+    Zip,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+pub enum Compress {
+    Gzip,
+    Zstd,
+}
+
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, ValueEnum)]
+pub enum FileAttr {
+    Size,
+    Mtime,
+    Sha256,
+    Lang,
+    Mode,
+    Tokens,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum HeaderStyle {
+    #[default]
+    Banner,
+    Comment,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum XmlSchema {
+    #[default]
+    V1,
+    V2,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum PathMode {
+    #[default]
+    Relative,
+    Absolute,
+    Root,
+    Anon,
 }
 
 #[derive(Args, Debug, Default)]
@@ -121,3 +324,89 @@ pub struct CleanArgs {
     #[arg(value_name = "DIR", num_args = 0..)]
     pub dirs: Vec<String>,
 }
+
+#[derive(Args, Debug, Default)]
+pub struct ValidateArgs {
+    #[arg(long = "print-schema")]
+    pub print_schema: bool,
+
+    #[arg(value_name = "FILE", required_unless_present = "print_schema")]
+    pub file: Option<std::path::PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct UnpackArgs {
+    #[arg(value_name = "BUNDLE", required = true, num_args = 1..)]
+    pub bundles: Vec<std::path::PathBuf>,
+
+    #[arg(
+        short = 'C',
+        long = "directory",
+        value_name = "DIR",
+        default_value = "."
+    )]
+    pub directory: std::path::PathBuf,
+
+    #[arg(long = "dry-run")]
+    pub dry_run: bool,
+
+    #[arg(long = "existing", value_enum, default_value_t = ExistingFiles::Error)]
+    pub existing: ExistingFiles,
+
+    #[arg(long = "allow-escape")]
+    pub allow_escape: bool,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum ExistingFiles {
+    #[default]
+    Error,
+    Skip,
+    Overwrite,
+}
+
+#[derive(Args, Debug)]
+pub struct ApplyArgs {
+    #[arg(value_name = "RESPONSE", default_value = "-")]
+    pub response: std::path::PathBuf,
+
+    #[arg(short = 'y', long = "yes")]
+    pub yes: bool,
+
+    #[arg(long = "stash")]
+    pub stash: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    #[arg(value_name = "OLD")]
+    pub old: std::path::PathBuf,
+
+    #[arg(value_name = "NEW")]
+    pub new: std::path::PathBuf,
+
+    #[arg(long = "format", value_enum, default_value_t = DiffFormat::Text)]
+    pub format: DiffFormat,
+
+    #[arg(long = "stat")]
+    pub stat: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct RerunArgs {
+    #[arg(value_name = "BUNDLE")]
+    pub bundle: std::path::PathBuf,
+
+    #[arg(short = 'C', long = "directory", value_name = "DIR")]
+    pub directory: Option<std::path::PathBuf>,
+
+    #[arg(long = "print")]
+    pub print: bool,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum DiffFormat {
+    #[default]
+    Text,
+    Json,
+}
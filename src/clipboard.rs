@@ -3,6 +3,7 @@ use std::process::{Command, Stdio};
 
 use anyhow::{Context, Result, bail};
 
+#[allow(clippy::needless_return)]
 pub fn copy_to_clipboard(content: &str) -> Result<()> {
     #[cfg(target_os = "macos")]
     {
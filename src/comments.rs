@@ -0,0 +1,180 @@
+use crate::syntax::{self, Token, TokenKind};
+
+// Comment-only lines are dropped, or left blank with `keep_lines` so line
+// numbers still match the file; a leading `#!` line is kept.
+pub fn strip(
+    text: &str,
+    language: Option<&str>,
+    keep_doc_comments: bool,
+    keep_lines: bool,
+) -> Option<String> {
+    let tokens = tokenize(text, language?)?;
+
+    let mut out = String::with_capacity(text.len());
+    let mut pos = 0;
+    let mut start = 0;
+    let mut stripped = false;
+
+    for token in tokens {
+        let end = start + token.text.len();
+        let token_start = start;
+        start = end;
+
+        let remove = match token.kind {
+            TokenKind::Comment => !(token_start == 0 && token.text.starts_with("#!")),
+            TokenKind::DocComment => !keep_doc_comments,
+            TokenKind::Code | TokenKind::Str => false,
+        };
+
+        if !remove {
+            if end > pos {
+                out.push_str(&text[pos.max(token_start)..end]);
+                pos = end;
+            }
+            continue;
+        }
+
+        stripped = true;
+        pos = end;
+
+        let line_head = &out[out.rfind('\n').map_or(0, |newline| newline + 1)..];
+        let rest = &text[end..];
+        let line_end = rest.find('\n').map_or(rest.len(), |newline| newline + 1);
+        let line_rest = &rest[..line_end];
+
+        if keep_lines && (is_blank(line_rest) || token.text.contains('\n')) {
+            // Leave the comment's lines blank so the code keeps its line numbers.
+            out.truncate(out.trim_end_matches([' ', '\t']).len());
+            push_line_breaks(&mut out, token.text);
+            pos = end + (rest.len() - rest.trim_start_matches([' ', '\t']).len());
+        } else if is_blank(line_head) && is_blank(line_rest) {
+            // The comment had the line to itself: drop the line.
+            out.truncate(out.len() - line_head.len());
+            pos = end + line_end;
+
+            if out.is_empty() || out.ends_with("\n\n") || out.ends_with("\n\r\n") {
+                pos += skip_blank_lines(&text[pos..]);
+            }
+        } else if is_blank(line_head) {
+            // Code follows on the same line: keep the indentation only.
+            pos = end + (rest.len() - rest.trim_start_matches([' ', '\t']).len());
+        } else {
+            out.truncate(out.trim_end_matches([' ', '\t']).len());
+            let glued = !rest.starts_with(char::is_whitespace) && !rest.is_empty();
+            if glued {
+                out.push(' ');
+            }
+        }
+    }
+
+    stripped.then_some(out)
+}
+
+fn push_line_breaks(out: &mut String, comment: &str) {
+    for (index, _) in comment.match_indices('\n') {
+        if comment[..index].ends_with('\r') {
+            out.push('\r');
+        }
+        out.push('\n');
+    }
+}
+
+// Markdown `<!-- -->` blocks are usually content, so Markdown is left alone.
+fn tokenize<'a>(text: &'a str, language: &str) -> Option<Vec<Token<'a>>> {
+    match language {
+        "markdown" => None,
+        "html" | "xml" => Some(tokenize_markup(text)),
+        _ => syntax::syntax_for(language).map(|syntax| syntax::tokenize(text, syntax)),
+    }
+}
+
+pub fn tokenize_markup(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut code_start = 0;
+    let mut pos = 0;
+
+    while pos < text.len() {
+        let rest = &text[pos..];
+
+        let matched = if rest.starts_with("<![CDATA[") {
+            Some((TokenKind::Str, find_end(rest, 9, "]]>")))
+        } else if rest.starts_with("<!--") {
+            Some((TokenKind::Comment, find_end(rest, 4, "-->")))
+        } else if rest.starts_with('<')
+            && rest[1..].starts_with(|c: char| c.is_ascii_alphabetic() || "/?!".contains(c))
+        {
+            Some((TokenKind::Str, tag_end(rest)))
+        } else {
+            None
+        };
+
+        match matched {
+            Some((kind, len)) => {
+                if code_start < pos {
+                    tokens.push(Token {
+                        kind: TokenKind::Code,
+                        text: &text[code_start..pos],
+                    });
+                }
+
+                tokens.push(Token {
+                    kind,
+                    text: &text[pos..pos + len],
+                });
+                pos += len;
+                code_start = pos;
+            }
+            None => {
+                pos += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+    }
+
+    if code_start < text.len() {
+        tokens.push(Token {
+            kind: TokenKind::Code,
+            text: &text[code_start..],
+        });
+    }
+
+    tokens
+}
+
+fn find_end(rest: &str, from: usize, close: &str) -> usize {
+    rest[from..]
+        .find(close)
+        .map_or(rest.len(), |offset| from + offset + close.len())
+}
+
+fn tag_end(rest: &str) -> usize {
+    let mut quote = None;
+
+    for (offset, c) in rest.char_indices().skip(1) {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return offset + 1,
+            (None, _) => {}
+        }
+    }
+
+    rest.len()
+}
+
+fn skip_blank_lines(text: &str) -> usize {
+    let mut skipped = 0;
+
+    for line in text.split_inclusive('\n') {
+        if !line.ends_with('\n') || !is_blank(line) {
+            break;
+        }
+        skipped += line.len();
+    }
+
+    skipped
+}
+
+fn is_blank(text: &str) -> bool {
+    text.trim().is_empty()
+}
@@ -0,0 +1,106 @@
+use std::io::{Read, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Zstd => "zst",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?;
+
+        [Self::Gzip, Self::Zstd]
+            .into_iter()
+            .find(|compression| ext.eq_ignore_ascii_case(compression.extension()))
+    }
+}
+
+pub enum Encoder<W: Write> {
+    Plain(W),
+    Gzip(flate2::write::GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> Encoder<W> {
+    pub fn new(compression: Option<Compression>, out: W) -> Result<Self> {
+        Ok(match compression {
+            None => Self::Plain(out),
+            Some(Compression::Gzip) => Self::Gzip(flate2::write::GzEncoder::new(
+                out,
+                flate2::Compression::default(),
+            )),
+            Some(Compression::Zstd) => Self::Zstd(
+                zstd::Encoder::new(out, zstd::DEFAULT_COMPRESSION_LEVEL)
+                    .context("Cannot start zstd compression")?,
+            ),
+        })
+    }
+
+    pub fn finish(self) -> std::io::Result<W> {
+        match self {
+            Self::Plain(out) => Ok(out),
+            Self::Gzip(encoder) => encoder.finish(),
+            Self::Zstd(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Plain(out) => out.write(buf),
+            Self::Gzip(encoder) => encoder.write(buf),
+            Self::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Plain(out) => out.flush(),
+            Self::Gzip(encoder) => encoder.flush(),
+            Self::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+// Compression is recognised by the magic bytes, not the file name.
+pub fn read_to_string(path: &Path) -> Result<String> {
+    let bytes = std::fs::read(path)?;
+    let mut text = String::new();
+
+    if bytes.starts_with(GZIP_MAGIC) {
+        flate2::read::MultiGzDecoder::new(bytes.as_slice())
+            .read_to_string(&mut text)
+            .context("Cannot decompress gzip data")?;
+    } else if bytes.starts_with(ZSTD_MAGIC) {
+        zstd::Decoder::new(bytes.as_slice())
+            .context("Cannot start zstd decompression")?
+            .read_to_string(&mut text)
+            .context("Cannot decompress zstd data")?;
+    } else {
+        text = String::from_utf8(bytes).context("File is not valid UTF-8")?;
+    }
+
+    Ok(text)
+}
@@ -1,18 +1,35 @@
+use std::ffi::OsString;
+use std::io::IsTerminal;
+
 use anyhow::{Result, bail};
 
+use crate::budget::TokenBudget;
 use crate::cli;
-use crate::cli::MetadataSort;
+use crate::cli::{FileAttr, MetadataSort, Tokenizer, XmlSchema};
+use crate::compress::Compression;
+use crate::headers::TextHeaders;
+use crate::outline::Outline;
+use crate::paths::PathDisplay;
+use crate::prompt::Prompt;
+use crate::redact::Redactor;
+use crate::split::SplitLimit;
+use crate::template::Template;
+use crate::truncate::Truncation;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum OutputFormat {
     Xml,
     Text,
+    Html,
+    Tar,
+    Zip,
 }
 
 #[derive(Debug)]
 pub struct RunConfig {
     pub output: Option<std::path::PathBuf>,
     pub copy_to_clipboard: bool,
+    pub write_to_stdout: bool,
     pub recursive: bool,
     pub format: OutputFormat,
     pub exts: Vec<String>,
@@ -27,14 +44,43 @@ pub struct RunConfig {
     pub show_dir_list: bool,
     pub show_metadata: bool,
     pub metadata_sort: MetadataSort,
+    pub file_attrs: Vec<FileAttr>,
+    pub tokenizer: Tokenizer,
     pub include_binary: bool,
+    pub line_numbers: bool,
+    pub notebook_outputs: bool,
+    pub redactor: Redactor,
+    pub redact: bool,
+    pub fail_on_secret: bool,
+    pub strip_comments: bool,
+    pub keep_doc_comments: bool,
+    pub minify_whitespace: Option<u8>,
+    pub outline: Option<Outline>,
+    pub truncation: Option<Truncation>,
+    pub text_headers: TextHeaders,
+    pub xml_schema: XmlSchema,
+    pub path_display: PathDisplay,
+    pub template: Option<Template>,
+    pub split: Option<SplitLimit>,
+    pub compression: Option<Compression>,
+    pub manifest: bool,
+    pub prompt: Option<Prompt>,
+    pub budget: Option<TokenBudget>,
     pub clean_concat_files: bool,
     pub inputs: Vec<String>,
     pub had_user_args: bool,
+    pub arguments: Vec<String>,
 }
 
 impl RunConfig {
-    pub fn from_cli(cli: cli::Cli, had_user_args: bool) -> Result<Self> {
+    pub fn from_cli(cli: cli::Cli, raw_args: &[OsString]) -> Result<Self> {
+        let had_user_args = raw_args.len() > 1;
+        let arguments = raw_args
+            .iter()
+            .skip(1)
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+
         let mut inputs = cli.run.inputs;
         if inputs.is_empty() {
             inputs.push(".".to_string());
@@ -46,12 +92,205 @@ impl RunConfig {
             _ => true,
         };
 
-        let format = if cli.run.text {
-            OutputFormat::Text
+        let format = match (cli.run.text, cli.run.format) {
+            (true, Some(cli::Format::Text) | None) => OutputFormat::Text,
+            (true, Some(_)) => bail!("Cannot pass both --text and a different --format."),
+            (false, None | Some(cli::Format::Xml)) => OutputFormat::Xml,
+            (false, Some(cli::Format::Text)) => OutputFormat::Text,
+            (false, Some(cli::Format::Html)) => OutputFormat::Html,
+            (false, Some(cli::Format::Tar)) => OutputFormat::Tar,
+            (false, Some(cli::Format::Zip)) => OutputFormat::Zip,
+        };
+
+        let write_to_stdout = cli.run.output.as_deref() == Some(std::path::Path::new("-"))
+            || (cli.run.stdout && !std::io::stdout().is_terminal());
+        if write_to_stdout && cli.run.clipboard {
+            bail!("Cannot write to both stdout and the clipboard.");
+        }
+
+        if format.is_archive() {
+            if cli.run.clipboard {
+                bail!(
+                    "Cannot copy a --format {} archive to the clipboard.",
+                    format.as_str()
+                );
+            }
+            if cli.run.template.is_some() {
+                bail!(
+                    "Cannot combine --template with --format {}.",
+                    format.as_str()
+                );
+            }
+        }
+
+        // Templates lay out everything xml and text output carries, whatever
+        // the --format.
+        let layout =
+            cli.run.template.is_some() || matches!(format, OutputFormat::Xml | OutputFormat::Text);
+
+        let split = match (cli.run.split_size.as_deref(), cli.run.split_tokens) {
+            (Some(size), _) => Some(SplitLimit::Bytes(parse_size(size)?)),
+            (None, Some(0)) => bail!("--split-tokens must be greater than zero."),
+            (None, Some(tokens)) => Some(SplitLimit::Tokens(tokens, cli.run.tokenizer)),
+            (None, None) => None,
+        };
+
+        if split.is_some() {
+            if !layout {
+                bail!(
+                    "--split-size and --split-tokens only support xml and text output, not --format {}.",
+                    format.as_str()
+                );
+            }
+            if write_to_stdout {
+                bail!("Cannot split output that is written to stdout.");
+            }
+        }
+
+        let compression = match cli.run.compress {
+            Some(cli::Compress::Gzip) => Some(Compression::Gzip),
+            Some(cli::Compress::Zstd) => Some(Compression::Zstd),
+            None => cli.run.output.as_deref().and_then(Compression::from_path),
+        };
+
+        if let Some(compression) = compression {
+            if cli.run.clipboard {
+                bail!(
+                    "Cannot copy {} compressed output to the clipboard.",
+                    compression.as_str()
+                );
+            }
+            if format == OutputFormat::Zip {
+                bail!(
+                    "Zip archives are already compressed; use --format tar with --compress {}.",
+                    compression.as_str()
+                );
+            }
+            if split.is_some() {
+                bail!("Cannot combine --compress with --split-size or --split-tokens.");
+            }
+        }
+
+        let customizes_headers = cli.run.header_style.is_some()
+            || cli.run.banner_width.is_some()
+            || cli.run.banner_chars.is_some()
+            || cli.run.compact_headers;
+        if customizes_headers && format != OutputFormat::Text && cli.run.template.is_none() {
+            bail!(
+                "--header-style, --banner-width, --banner-chars and --compact-headers only apply to text output, not --format {}.",
+                format.as_str()
+            );
+        }
+
+        let text_headers = TextHeaders::new(
+            cli.run.header_style.unwrap_or_default(),
+            cli.run.banner_width,
+            cli.run.banner_chars.as_deref(),
+            cli.run.compact_headers,
+        )?;
+
+        if cli.run.manifest && !layout {
+            bail!(
+                "--manifest only applies to xml and text output, not --format {}.",
+                format.as_str()
+            );
+        }
+
+        if (cli.run.prompt.is_some() || cli.run.prompt_file.is_some()) && !layout {
+            bail!(
+                "--prompt only applies to xml and text output, not --format {}.",
+                format.as_str()
+            );
+        }
+
+        if cli.run.notebook_outputs && matches!(format, OutputFormat::Tar | OutputFormat::Zip) {
+            bail!(
+                "--format {} stores notebooks unchanged and cannot render their outputs.",
+                format.as_str()
+            );
+        }
+
+        if cli.run.strip_comments && matches!(format, OutputFormat::Tar | OutputFormat::Zip) {
+            bail!(
+                "--format {} stores files unchanged and cannot strip comments.",
+                format.as_str()
+            );
+        }
+        if cli.run.minify_whitespace.is_some()
+            && matches!(format, OutputFormat::Tar | OutputFormat::Zip)
+        {
+            bail!(
+                "--format {} stores files unchanged and cannot minify whitespace.",
+                format.as_str()
+            );
+        }
+        if cli.run.minify_whitespace.is_some() && cli.run.line_numbers {
+            bail!(
+                "Cannot combine --minify-whitespace with --line-numbers: minifying drops and joins lines, so the numbers would not match the file."
+            );
+        }
+
+        let outline = if cli.run.outline {
+            if matches!(format, OutputFormat::Tar | OutputFormat::Zip) {
+                bail!(
+                    "--format {} stores files unchanged and cannot outline them.",
+                    format.as_str()
+                );
+            }
+            Some(Outline::new(&cli.run.full)?)
         } else {
-            OutputFormat::Xml
+            None
+        };
+
+        let truncation = Truncation::new(
+            cli.run.truncate_lines,
+            cli.run.truncate_bytes,
+            cli.run.truncate_strategy.unwrap_or_default(),
+            &cli.run.truncate_glob,
+        )?;
+        if truncation.is_none() && cli.run.truncate_strategy.is_some() {
+            bail!("--truncate-strategy needs --truncate-lines or --truncate-bytes.");
+        }
+        if truncation.is_some() && matches!(format, OutputFormat::Tar | OutputFormat::Zip) {
+            bail!(
+                "--format {} stores files unchanged and cannot truncate them.",
+                format.as_str()
+            );
+        }
+
+        let budget = match cli.run.max_tokens {
+            None => None,
+            Some(0) => bail!("--max-tokens must be greater than zero."),
+            Some(max_tokens) => {
+                if !layout {
+                    bail!(
+                        "--max-tokens only applies to xml and text output, not --format {}.",
+                        format.as_str()
+                    );
+                }
+                if split.is_some() {
+                    bail!("Cannot combine --max-tokens with --split-size or --split-tokens.");
+                }
+
+                Some(TokenBudget {
+                    max_tokens,
+                    priority: cli.run.priority,
+                    rank: cli.run.rank.unwrap_or_default(),
+                })
+            }
         };
 
+        let template = cli
+            .run
+            .template
+            .as_deref()
+            .map(Template::load)
+            .transpose()?;
+
+        let mut file_attrs = cli.run.file_attrs;
+        file_attrs.sort();
+        file_attrs.dedup();
+
         let exts = normalize_exts(cli.run.exts);
         let ignore_exts = normalize_exts(cli.run.ignore_exts);
         let exclude_globs = normalize_exclude_globs(cli.run.exclude_globs);
@@ -59,6 +298,7 @@ impl RunConfig {
         Ok(Self {
             output: cli.run.output,
             copy_to_clipboard: cli.run.clipboard,
+            write_to_stdout,
             recursive,
             format,
             exts,
@@ -73,10 +313,36 @@ impl RunConfig {
             show_dir_list: !cli.run.no_dir_list,
             show_metadata: !cli.run.no_metadata,
             metadata_sort: cli.run.metadata_sort,
+            file_attrs,
+            tokenizer: cli.run.tokenizer,
             include_binary: cli.run.include_binary,
+            line_numbers: cli.run.line_numbers,
+            notebook_outputs: cli.run.notebook_outputs,
+            redactor: Redactor::new(&cli.run.redact_rules)?,
+            redact: !cli.run.no_redact,
+            fail_on_secret: cli.run.fail_on_secret,
+            strip_comments: cli.run.strip_comments,
+            keep_doc_comments: cli.run.keep_doc_comments,
+            minify_whitespace: cli.run.minify_whitespace,
+            outline,
+            truncation,
+            text_headers,
+            xml_schema: cli.run.xml_schema,
+            path_display: PathDisplay::new(cli.run.paths)?,
+            template,
+            split,
+            compression,
+            manifest: cli.run.manifest,
+            prompt: Prompt::new(
+                cli.run.prompt.as_deref(),
+                cli.run.prompt_file.as_deref(),
+                cli.run.prompt_position.unwrap_or_default(),
+            )?,
+            budget,
             clean_concat_files: !cli.run.no_clean_concat,
             inputs,
             had_user_args,
+            arguments,
         })
     }
 
@@ -87,52 +353,123 @@ impl RunConfig {
 
         eprintln!("----------------------------------------");
         eprintln!("Configuration:");
-        eprintln!("Inputs: {}", self.inputs.join(" "));
-
-        match &self.output {
-            Some(path) => eprintln!("Output File (requested): \"{}\"", path.display()),
-            None => eprintln!("Output File (requested): <auto>"),
-        }
-
-        eprintln!("Copy To Clipboard: {}", self.copy_to_clipboard);
-        eprintln!("Format: {}", self.format.as_str());
-        eprintln!("Recursive: {}", self.recursive);
-        eprintln!("Include Hidden: {}", self.include_hidden);
-        eprintln!("Show Tree: {}", self.show_tree);
-        eprintln!("Show Dir List: {}", self.show_dir_list);
-        eprintln!("Show Metadata: {}", self.show_metadata);
-        eprintln!("Metadata Sort: {:?}", self.metadata_sort);
-        eprintln!("Purge Pycache (in CWD): {}", self.purge_pycache);
-        eprintln!("Include Binary: {}", self.include_binary);
-        eprintln!("Clean _concat-* in CWD: {}", self.clean_concat_files);
-        eprintln!("Had User Args: {}", self.had_user_args);
-
-        if self.exts.is_empty() {
-            eprintln!("Include Extensions: All");
-        } else {
-            eprintln!("Include Extensions: {}", self.exts.join(" "));
-        }
-
-        if self.include_globs.is_empty() {
-            eprintln!("Include Globs: All");
-        } else {
-            eprintln!("Include Globs: {}", self.include_globs.join(" "));
-        }
-
-        if self.exclude_globs.is_empty() {
-            eprintln!("Exclude Globs: None");
-        } else {
-            eprintln!("Exclude Globs: {}", self.exclude_globs.join(" "));
+        for (name, value) in self.summary() {
+            eprintln!("{name}: {value}");
         }
+        eprintln!("----------------------------------------");
+    }
 
-        if self.ignore_exts.is_empty() {
-            eprintln!("Ignore Extensions: None");
-        } else {
-            eprintln!("Ignore Extensions: {}", self.ignore_exts.join(" "));
-        }
+    pub fn summary(&self) -> Vec<(&'static str, String)> {
+        let list = |items: &[String], empty: &str| {
+            if items.is_empty() {
+                empty.to_string()
+            } else {
+                items.join(" ")
+            }
+        };
+        let or_none = |value: Option<String>| value.unwrap_or_else(|| "None".to_string());
 
-        eprintln!("Debug Mode: {}", self.debug);
-        eprintln!("----------------------------------------");
+        vec![
+            ("Inputs", self.inputs.join(" ")),
+            (
+                "Output File (requested)",
+                self.output.as_ref().map_or("<auto>".to_string(), |path| {
+                    format!("\"{}\"", path.display())
+                }),
+            ),
+            ("Copy To Clipboard", self.copy_to_clipboard.to_string()),
+            ("Write To Stdout", self.write_to_stdout.to_string()),
+            ("Format", self.format.as_str().to_string()),
+            ("Recursive", self.recursive.to_string()),
+            ("Include Hidden", self.include_hidden.to_string()),
+            ("Show Tree", self.show_tree.to_string()),
+            ("Show Dir List", self.show_dir_list.to_string()),
+            ("Show Metadata", self.show_metadata.to_string()),
+            ("Metadata Sort", format!("{:?}", self.metadata_sort)),
+            (
+                "File Attributes",
+                or_none((!self.file_attrs.is_empty()).then(|| {
+                    let names: Vec<&str> = self.file_attrs.iter().map(|attr| attr.name()).collect();
+                    names.join(",")
+                })),
+            ),
+            ("Tokenizer", self.tokenizer.name().to_string()),
+            ("Purge Pycache (in CWD)", self.purge_pycache.to_string()),
+            ("Include Binary", self.include_binary.to_string()),
+            ("Line Numbers", self.line_numbers.to_string()),
+            ("Notebook Outputs", self.notebook_outputs.to_string()),
+            (
+                "Redact Secrets",
+                if self.redact {
+                    self.redactor.describe()
+                } else {
+                    "false".to_string()
+                },
+            ),
+            ("Fail On Secret", self.fail_on_secret.to_string()),
+            (
+                "Strip Comments",
+                match (self.strip_comments, self.keep_doc_comments) {
+                    (false, _) => "false".to_string(),
+                    (true, false) => "true".to_string(),
+                    (true, true) => "true (keeping doc comments)".to_string(),
+                },
+            ),
+            (
+                "Minify Whitespace",
+                or_none(self.minify_whitespace.map(|level| format!("level {level}"))),
+            ),
+            (
+                "Outline",
+                self.outline
+                    .as_ref()
+                    .map_or_else(|| "false".to_string(), Outline::describe),
+            ),
+            (
+                "Truncation",
+                or_none(self.truncation.as_ref().map(Truncation::describe)),
+            ),
+            ("Text Headers", self.text_headers.describe()),
+            ("Paths", format!("{:?}", self.path_display.mode())),
+            ("XML Schema", format!("{:?}", self.xml_schema)),
+            (
+                "Template",
+                or_none(
+                    self.template
+                        .as_ref()
+                        .map(|template| template.name().to_string()),
+                ),
+            ),
+            ("Split", or_none(self.split.map(|limit| limit.describe()))),
+            (
+                "Compression",
+                or_none(
+                    self.compression
+                        .map(|compression| compression.as_str().to_string()),
+                ),
+            ),
+            ("Manifest", self.manifest.to_string()),
+            (
+                "Prompt",
+                self.prompt
+                    .as_ref()
+                    .map_or_else(|| "false".to_string(), Prompt::describe),
+            ),
+            (
+                "Token Budget",
+                or_none(self.budget.as_ref().map(TokenBudget::describe)),
+            ),
+            (
+                "Clean _concat-* in CWD",
+                self.clean_concat_files.to_string(),
+            ),
+            ("Had User Args", self.had_user_args.to_string()),
+            ("Include Extensions", list(&self.exts, "All")),
+            ("Include Globs", list(&self.include_globs, "All")),
+            ("Exclude Globs", list(&self.exclude_globs, "None")),
+            ("Ignore Extensions", list(&self.ignore_exts, "None")),
+            ("Debug Mode", self.debug.to_string()),
+        ]
     }
 }
 
@@ -168,6 +505,39 @@ impl OutputFormat {
         match self {
             Self::Xml => "xml",
             Self::Text => "text",
+            Self::Html => "html",
+            Self::Tar => "tar",
+            Self::Zip => "zip",
+        }
+    }
+
+    pub fn is_archive(self) -> bool {
+        matches!(self, Self::Tar | Self::Zip)
+    }
+}
+
+fn parse_size(value: &str) -> Result<usize> {
+    let trimmed = value.trim();
+    let split_at = trimmed
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(trimmed.len());
+    let (digits, suffix) = trimmed.split_at(split_at);
+
+    let multiplier = match suffix.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1024,
+        "m" | "mb" | "mib" => 1024 * 1024,
+        "g" | "gb" | "gib" => 1024 * 1024 * 1024,
+        _ => {
+            bail!("Invalid size \"{value}\": expected a number with an optional K, M or G suffix.")
+        }
+    };
+
+    match digits.parse::<usize>() {
+        Ok(0) => bail!("--split-size must be greater than zero."),
+        Ok(number) => Ok(number * multiplier),
+        Err(_) => {
+            bail!("Invalid size \"{value}\": expected a number with an optional K, M or G suffix.")
         }
     }
 }
@@ -0,0 +1,321 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+
+use crate::bundle::{self, BundleFile};
+use crate::cli::{self, DiffFormat};
+
+#[derive(Clone, Copy, Default)]
+struct FileStats {
+    lines: usize,
+    characters: usize,
+    tokens: Option<usize>,
+}
+
+struct LoadedBundle {
+    files: BTreeMap<String, BundleFile>,
+    stats: BTreeMap<String, FileStats>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Added,
+    Removed,
+    Changed,
+    Unchanged,
+}
+
+struct FileDelta {
+    path: String,
+    status: Status,
+    old: Option<FileStats>,
+    new: Option<FileStats>,
+    patch: Option<String>,
+}
+
+pub fn run(args: cli::DiffArgs) -> Result<()> {
+    let old_source = read_bundle(&args.old)?;
+    let new_source = read_bundle(&args.new)?;
+
+    // Text bundles end every file with a newline, so when only one side is a
+    // text bundle the XML side gets one too rather than reporting a change.
+    let mixed = bundle::is_xml_bundle(&old_source) != bundle::is_xml_bundle(&new_source);
+    let old = load_bundle(&args.old, &old_source, mixed)?;
+    let new = load_bundle(&args.new, &new_source, mixed)?;
+    let deltas = compare(&old, &new);
+
+    match args.format {
+        DiffFormat::Text => print!("{}", render_text(&deltas, &old, &new, args.stat)),
+        DiffFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&render_json(&args, &deltas, &old, &new))?
+        ),
+    }
+
+    Ok(())
+}
+
+fn read_bundle(path: &Path) -> Result<String> {
+    crate::compress::read_to_string(path)
+        .with_context(|| format!("Cannot read bundle \"{}\".", path.display()))
+}
+
+fn load_bundle(path: &Path, source: &str, final_newlines: bool) -> Result<LoadedBundle> {
+    let mut files = bundle::parse_bundle(source)
+        .with_context(|| format!("Cannot parse bundle \"{}\".", path.display()))?;
+    let metadata = bundle::parse_bundle_metadata(source)
+        .with_context(|| format!("Cannot parse bundle \"{}\".", path.display()))?;
+
+    if final_newlines && bundle::is_xml_bundle(source) {
+        for file in files.iter_mut().filter(|file| {
+            !file.is_binary && !file.content.is_empty() && !file.content.ends_with(b"\n")
+        }) {
+            file.content.push(b'\n');
+        }
+    }
+
+    let mut stats: BTreeMap<String, FileStats> = files
+        .iter()
+        .map(|file| (file.path.clone(), content_stats(file)))
+        .collect();
+
+    for entry in metadata {
+        let Some(file_stats) = stats.get_mut(&entry.path) else {
+            continue;
+        };
+
+        if let Some(lines) = entry.lines {
+            file_stats.lines = lines;
+        }
+        if let Some(characters) = entry.characters {
+            file_stats.characters = characters;
+        }
+        file_stats.tokens = entry.tokens;
+    }
+
+    let files = files
+        .into_iter()
+        .map(|file| (file.path.clone(), file))
+        .collect();
+
+    Ok(LoadedBundle { files, stats })
+}
+
+fn content_stats(file: &BundleFile) -> FileStats {
+    if file.is_binary {
+        return FileStats {
+            lines: file.content.iter().filter(|&&byte| byte == b'\n').count(),
+            characters: file.content.len(),
+            tokens: None,
+        };
+    }
+
+    let text = String::from_utf8_lossy(&file.content);
+    FileStats {
+        lines: text.lines().count(),
+        characters: text.chars().count(),
+        tokens: None,
+    }
+}
+
+fn compare(old: &LoadedBundle, new: &LoadedBundle) -> Vec<FileDelta> {
+    let paths: BTreeSet<&String> = old.files.keys().chain(new.files.keys()).collect();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let old_file = old.files.get(path);
+            let new_file = new.files.get(path);
+
+            let status = match (old_file, new_file) {
+                (None, Some(_)) => Status::Added,
+                (Some(_), None) => Status::Removed,
+                (Some(a), Some(b)) if a.content == b.content => Status::Unchanged,
+                _ => Status::Changed,
+            };
+
+            let patch = match (status, old_file, new_file) {
+                (Status::Changed, Some(a), Some(b)) => Some(unified_diff(path, a, b)),
+                _ => None,
+            };
+
+            FileDelta {
+                path: path.clone(),
+                status,
+                old: old.stats.get(path).copied(),
+                new: new.stats.get(path).copied(),
+                patch,
+            }
+        })
+        .collect()
+}
+
+fn unified_diff(path: &str, old: &BundleFile, new: &BundleFile) -> String {
+    if old.is_binary || new.is_binary {
+        return format!("Binary files a/{path} and b/{path} differ\n");
+    }
+
+    let old_text = String::from_utf8_lossy(&old.content);
+    let new_text = String::from_utf8_lossy(&new.content);
+    let diff = similar::TextDiff::from_lines(old_text.as_ref(), new_text.as_ref());
+
+    diff.unified_diff()
+        .context_radius(3)
+        .header(&format!("a/{path}"), &format!("b/{path}"))
+        .to_string()
+}
+
+fn render_text(deltas: &[FileDelta], old: &LoadedBundle, new: &LoadedBundle, stat: bool) -> String {
+    let count = |status: Status| deltas.iter().filter(|delta| delta.status == status).count();
+    let mut out = format!(
+        "Added: {}, removed: {}, changed: {}, unchanged: {}\n",
+        count(Status::Added),
+        count(Status::Removed),
+        count(Status::Changed),
+        count(Status::Unchanged)
+    );
+
+    let listed: Vec<&FileDelta> = deltas
+        .iter()
+        .filter(|delta| delta.status != Status::Unchanged)
+        .collect();
+
+    if !listed.is_empty() {
+        out.push('\n');
+    }
+
+    for delta in &listed {
+        let line = match (delta.status, delta.old, delta.new) {
+            (Status::Added, _, Some(new)) => format!("+ {} ({})", delta.path, stats_text(new)),
+            (Status::Removed, Some(old), _) => format!("- {} ({})", delta.path, stats_text(old)),
+            (_, Some(old), Some(new)) => {
+                format!("~ {} ({})", delta.path, change_text(old, new))
+            }
+            _ => continue,
+        };
+
+        out.push_str(&line);
+        out.push('\n');
+    }
+
+    let old_totals = totals(old);
+    let new_totals = totals(new);
+    out.push_str(&format!(
+        "\nTotals: files {}, {}\n",
+        delta_text(old.files.len(), new.files.len()),
+        change_text(old_totals, new_totals)
+    ));
+
+    if !stat {
+        for patch in listed.iter().filter_map(|delta| delta.patch.as_deref()) {
+            out.push('\n');
+            out.push_str(patch);
+        }
+    }
+
+    out
+}
+
+fn stats_text(stats: FileStats) -> String {
+    let mut text = format!("lines: {}, chars: {}", stats.lines, stats.characters);
+    if let Some(tokens) = stats.tokens {
+        text.push_str(&format!(", tokens: {tokens}"));
+    }
+    text
+}
+
+fn change_text(old: FileStats, new: FileStats) -> String {
+    let mut text = format!(
+        "lines {}, chars {}",
+        delta_text(old.lines, new.lines),
+        delta_text(old.characters, new.characters)
+    );
+    if let (Some(old_tokens), Some(new_tokens)) = (old.tokens, new.tokens) {
+        text.push_str(&format!(", tokens {}", delta_text(old_tokens, new_tokens)));
+    }
+    text
+}
+
+fn delta_text(old: usize, new: usize) -> String {
+    format!("{old} -> {new} ({:+})", signed_delta(old, new))
+}
+
+fn signed_delta(old: usize, new: usize) -> i64 {
+    new as i64 - old as i64
+}
+
+fn totals(bundle: &LoadedBundle) -> FileStats {
+    let all_tokens = bundle.stats.values().all(|stats| stats.tokens.is_some());
+
+    FileStats {
+        lines: bundle.stats.values().map(|stats| stats.lines).sum(),
+        characters: bundle.stats.values().map(|stats| stats.characters).sum(),
+        tokens: all_tokens.then(|| bundle.stats.values().filter_map(|stats| stats.tokens).sum()),
+    }
+}
+
+fn render_json(
+    args: &cli::DiffArgs,
+    deltas: &[FileDelta],
+    old: &LoadedBundle,
+    new: &LoadedBundle,
+) -> Value {
+    let entries = |status: Status| -> Vec<Value> {
+        deltas
+            .iter()
+            .filter(|delta| delta.status == status)
+            .map(|delta| file_json(delta, args.stat))
+            .collect()
+    };
+
+    json!({
+        "old": args.old.display().to_string(),
+        "new": args.new.display().to_string(),
+        "added": entries(Status::Added),
+        "removed": entries(Status::Removed),
+        "changed": entries(Status::Changed),
+        "unchanged": deltas.iter().filter(|delta| delta.status == Status::Unchanged).count(),
+        "totals": {
+            "files": count_json(Some(old.files.len()), Some(new.files.len())),
+            "lines": count_json(Some(totals(old).lines), Some(totals(new).lines)),
+            "characters": count_json(Some(totals(old).characters), Some(totals(new).characters)),
+            "tokens": count_json(totals(old).tokens, totals(new).tokens),
+        },
+    })
+}
+
+fn file_json(delta: &FileDelta, stat: bool) -> Value {
+    let old = delta.old.unwrap_or_default();
+    let new = delta.new.unwrap_or_default();
+    let (old_tokens, new_tokens) = match delta.status {
+        Status::Added => (Some(0), new.tokens),
+        Status::Removed => (old.tokens, Some(0)),
+        _ => (old.tokens, new.tokens),
+    };
+
+    let mut value = json!({
+        "path": delta.path,
+        "lines": count_json(Some(old.lines), Some(new.lines)),
+        "characters": count_json(Some(old.characters), Some(new.characters)),
+        "tokens": count_json(old_tokens, new_tokens),
+    });
+
+    if !stat && let Some(patch) = &delta.patch {
+        value["diff"] = Value::String(patch.clone());
+    }
+
+    value
+}
+
+fn count_json(old: Option<usize>, new: Option<usize>) -> Value {
+    match (old, new) {
+        (Some(old), Some(new)) => json!({
+            "old": old,
+            "new": new,
+            "delta": signed_delta(old, new),
+        }),
+        _ => Value::Null,
+    }
+}
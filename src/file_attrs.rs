@@ -0,0 +1,98 @@
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use sha2::{Digest, Sha256};
+
+use crate::cli::FileAttr;
+use crate::language;
+
+impl FileAttr {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Size => "size",
+            Self::Mtime => "mtime",
+            Self::Sha256 => "sha256",
+            Self::Lang => "lang",
+            Self::Mode => "mode",
+            Self::Tokens => "tokens",
+        }
+    }
+}
+
+pub fn collect(attrs: &[FileAttr], path: &Path, bytes: &[u8]) -> Vec<(FileAttr, String)> {
+    if attrs.is_empty() {
+        return Vec::new();
+    }
+
+    let metadata = std::fs::metadata(path).ok();
+
+    attrs
+        .iter()
+        .filter_map(|&attr| {
+            let value = match attr {
+                FileAttr::Size => Some(bytes.len().to_string()),
+                FileAttr::Mtime => metadata
+                    .as_ref()
+                    .and_then(|metadata| metadata.modified().ok())
+                    .map(format_timestamp),
+                FileAttr::Sha256 => Some(
+                    Sha256::digest(bytes)
+                        .iter()
+                        .map(|byte| format!("{byte:02x}"))
+                        .collect(),
+                ),
+                FileAttr::Lang => language::language_for_path(path).map(str::to_string),
+                FileAttr::Mode => metadata.as_ref().and_then(permission_bits),
+                FileAttr::Tokens => None,
+            };
+
+            value.map(|value| (attr, value))
+        })
+        .collect()
+}
+
+#[cfg(unix)]
+fn permission_bits(metadata: &std::fs::Metadata) -> Option<String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    Some(format!("{:03o}", metadata.permissions().mode() & 0o7777))
+}
+
+#[cfg(not(unix))]
+fn permission_bits(_metadata: &std::fs::Metadata) -> Option<String> {
+    None
+}
+
+pub fn format_timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() as i64)
+        .unwrap_or(0);
+
+    let days = seconds.div_euclid(86_400);
+    let of_day = seconds.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        of_day / 3600,
+        of_day % 3600 / 60,
+        of_day % 60
+    )
+}
+
+// Howard Hinnant's `civil_from_days`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
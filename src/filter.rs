@@ -49,6 +49,36 @@ pub fn filter_candidates(
             continue;
         }
 
+        // Unless the bundle goes to the clipboard, these are deleted once the
+        // secret scan has passed.
+        if config.clean_concat_files
+            && !config.copy_to_clipboard
+            && crate::cleanup::is_concat_output_in_cwd(file_path)
+        {
+            if config.verbose {
+                eprintln!(
+                    "Skipped file: \"{}\" (is a previous concat output)",
+                    file_path.display()
+                );
+            }
+
+            continue;
+        }
+
+        if let Some(output_path) = output_path
+            && config.split.is_some()
+            && crate::split::is_part_file(output_path, file_path)
+        {
+            if config.verbose {
+                eprintln!(
+                    "Skipped file: \"{}\" (is a previous split part)",
+                    file_path.display()
+                );
+            }
+
+            continue;
+        }
+
         let is_hidden = is_hidden_path(file_path);
         if is_hidden && !config.include_hidden {
             let explicit_or_include_hidden = ctx.explicit_file_inputs.contains(file_path)
@@ -0,0 +1,189 @@
+use std::path::Path;
+
+use anyhow::{Result, bail};
+
+use crate::cli::HeaderStyle;
+use crate::language;
+use crate::syntax;
+use crate::template;
+
+const DEFAULT_WIDTH: usize = 80;
+const DEFAULT_CHARS: [char; 3] = ['-', '*', '='];
+
+const MIN_WIDTH: usize = 16;
+
+#[derive(Clone, Copy, Debug)]
+pub struct TextHeaders {
+    pub style: HeaderStyle,
+    pub width: usize,
+    pub chars: [char; 3],
+    pub compact: bool,
+}
+
+struct Comment {
+    open: &'static str,
+    close: &'static str,
+}
+
+const HASH: Comment = Comment {
+    open: "#",
+    close: "",
+};
+
+impl TextHeaders {
+    pub fn new(
+        style: HeaderStyle,
+        width: Option<usize>,
+        chars: Option<&str>,
+        compact: bool,
+    ) -> Result<Self> {
+        let width = width.unwrap_or(DEFAULT_WIDTH);
+        if width < MIN_WIDTH {
+            bail!("--banner-width must be at least {MIN_WIDTH}.");
+        }
+
+        let chars = match chars {
+            None => DEFAULT_CHARS,
+            Some(chars) => parse_chars(chars)?,
+        };
+
+        Ok(Self {
+            style,
+            width,
+            chars,
+            compact,
+        })
+    }
+
+    pub fn describe(&self) -> String {
+        let style = match self.style {
+            HeaderStyle::Banner => "banner",
+            HeaderStyle::Comment => "comment",
+        };
+
+        if self.compact {
+            format!("{style}, compact")
+        } else {
+            format!(
+                "{style}, width {}, chars {}",
+                self.width,
+                String::from_iter(self.chars)
+            )
+        }
+    }
+
+    pub fn set_section_context(&self, ctx: &mut template::Context) {
+        ctx.set("compact", self.compact);
+        ctx.set("rule_above", rule(self.chars[0], self.width));
+        ctx.set("rule_below", rule(self.chars[1], self.width));
+        ctx.set("rule_end", rule(self.chars[2], self.width));
+    }
+
+    pub fn set_file_context(&self, ctx: &mut template::Context, path: &Path) {
+        let comment = self.comment_for(path);
+        ctx.set("compact", self.compact);
+        ctx.set("rule_above", self.comment_rule(&comment, self.chars[0]));
+        ctx.set("rule_below", self.comment_rule(&comment, self.chars[1]));
+        ctx.set("rule_end", self.comment_rule(&comment, self.chars[2]));
+        ctx.set("comment_open", format!("{} ", comment.open));
+        ctx.set(
+            "comment_close",
+            if comment.close.is_empty() {
+                String::new()
+            } else {
+                format!(" {}", comment.close)
+            },
+        );
+    }
+
+    fn comment_rule(&self, comment: &Comment, c: char) -> String {
+        if comment.open == "#" && self.style == HeaderStyle::Banner {
+            return rule(c, self.width);
+        }
+
+        // `--` is not allowed inside XML comments.
+        let c = if comment.close == "-->" && c == '-' {
+            '='
+        } else {
+            c
+        };
+
+        let mut markers = comment.open.len() + 1;
+        if !comment.close.is_empty() {
+            markers += comment.close.len() + 1;
+        }
+
+        wrap(comment, &rule(c, self.width.saturating_sub(markers)))
+    }
+
+    fn comment_for(&self, path: &Path) -> Comment {
+        if self.style == HeaderStyle::Banner {
+            return HASH;
+        }
+
+        let Some(language) = language::language_for_path(path) else {
+            return HASH;
+        };
+
+        if let Some(syntax) = syntax::syntax_for(language) {
+            if let Some(open) = syntax.line_comments.first() {
+                return Comment { open, close: "" };
+            }
+            if let Some((open, close)) = syntax.block_comments.first() {
+                return Comment { open, close };
+            }
+        }
+
+        match language {
+            "php" => Comment {
+                open: "//",
+                close: "",
+            },
+            "lua" | "haskell" => Comment {
+                open: "--",
+                close: "",
+            },
+            "erlang" => Comment {
+                open: "%",
+                close: "",
+            },
+            "ini" => Comment {
+                open: ";",
+                close: "",
+            },
+            _ => HASH,
+        }
+    }
+}
+
+fn wrap(comment: &Comment, text: &str) -> String {
+    if comment.close.is_empty() {
+        format!("{} {text}", comment.open)
+    } else {
+        format!("{} {text} {}", comment.open, comment.close)
+    }
+}
+
+fn rule(c: char, width: usize) -> String {
+    std::iter::repeat_n(c, width).collect()
+}
+
+fn parse_chars(value: &str) -> Result<[char; 3]> {
+    let chars: Vec<char> = value.chars().collect();
+    let chars = match chars.as_slice() {
+        [c] => [*c; 3],
+        [open, close, end] => [*open, *close, *end],
+        _ => bail!(
+            "--banner-chars takes one character or three (above a header, below it, after a section), not \"{value}\"."
+        ),
+    };
+
+    if chars
+        .iter()
+        .any(|c| c.is_alphanumeric() || c.is_whitespace())
+    {
+        bail!("--banner-chars must be punctuation, not \"{value}\".");
+    }
+
+    Ok(chars)
+}
@@ -0,0 +1,418 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use base64::Engine as _;
+
+use crate::config::RunConfig;
+use crate::language;
+use crate::output::{self, FileBody, FileMetadata};
+use crate::syntax::{self, Syntax, TokenKind};
+
+const STYLE: &str = r#"
+:root { color-scheme: light dark; --bg: #ffffff; --fg: #1f2328; --muted: #656d76; --border: #d0d7de; --code-bg: #f6f8fa; --kw: #cf222e; --str: #0a3069; --com: #6e7781; --num: #0550ae; }
+@media (prefers-color-scheme: dark) { :root { --bg: #0d1117; --fg: #e6edf3; --muted: #8d96a0; --border: #30363d; --code-bg: #161b22; --kw: #ff7b72; --str: #a5d6ff; --com: #8b949e; --num: #79c0ff; } }
+body { margin: 0 auto; max-width: 1100px; padding: 1.5rem; background: var(--bg); color: var(--fg); font: 15px/1.5 -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif; }
+h1 { margin: 0 0 .25rem; font-size: 1.6rem; }
+h2 { margin-top: 2rem; border-bottom: 1px solid var(--border); padding-bottom: .3rem; font-size: 1.2rem; }
+a { color: inherit; }
+pre, code, .path { font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; font-size: 13px; }
+pre { margin: 0; padding: .75rem 1rem; overflow-x: auto; background: var(--code-bg); border-radius: 6px; }
+table { border-collapse: collapse; width: 100%; }
+th, td { padding: .3rem .6rem; border-bottom: 1px solid var(--border); text-align: left; }
+th { cursor: pointer; user-select: none; white-space: nowrap; }
+th[aria-sort="ascending"]::after { content: " \25B2"; }
+th[aria-sort="descending"]::after { content: " \25BC"; }
+td.count { text-align: right; font-variant-numeric: tabular-nums; }
+details.file { margin: .75rem 0; border: 1px solid var(--border); border-radius: 6px; }
+details.file > summary { padding: .5rem .75rem; cursor: pointer; }
+details.file > pre { border-radius: 0 0 6px 6px; }
+.muted, .meta { color: var(--muted); }
+.meta { margin-left: .5rem; font-size: .85em; }
+.controls button { margin-right: .5rem; }
+.kw { color: var(--kw); }
+.str { color: var(--str); }
+.com { color: var(--com); font-style: italic; }
+.num { color: var(--num); }
+.ln { display: inline-block; margin-right: 1rem; color: var(--muted); text-align: right; user-select: none; }
+.error { color: var(--kw); }
+"#;
+
+const SCRIPT: &str = r#"
+document.querySelectorAll("table.sortable th").forEach(function (th, column) {
+  th.addEventListener("click", function () {
+    var table = th.closest("table");
+    var body = table.tBodies[0];
+    var numeric = th.dataset.type === "number";
+    var ascending = th.getAttribute("aria-sort") !== "ascending";
+    table.querySelectorAll("th").forEach(function (other) { other.removeAttribute("aria-sort"); });
+    th.setAttribute("aria-sort", ascending ? "ascending" : "descending");
+    Array.from(body.rows).sort(function (a, b) {
+      var x = a.cells[column].dataset.value, y = b.cells[column].dataset.value;
+      var order = numeric ? Number(x) - Number(y) : x.localeCompare(y, undefined, { numeric: true });
+      return ascending ? order : -order;
+    }).forEach(function (row) { body.appendChild(row); });
+  });
+});
+function setAllFiles(open) {
+  document.querySelectorAll("details.file").forEach(function (details) { details.open = open; });
+}
+"#;
+
+pub fn write_html_output(
+    config: &RunConfig,
+    matched_files: &[PathBuf],
+    tree: Option<&str>,
+    metadata: Option<&[FileMetadata]>,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let total = matched_files.len();
+
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html lang=\"en\">")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(
+        out,
+        "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">"
+    )?;
+    writeln!(out, "<title>concat report ({total} files)</title>")?;
+    writeln!(out, "<style>{STYLE}</style>")?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    writeln!(out, "<header>")?;
+    writeln!(out, "<h1>concat report</h1>")?;
+    writeln!(out, "<p class=\"muted\">{total} files</p>")?;
+    writeln!(out, "</header>")?;
+
+    if let Some(tree) = tree {
+        writeln!(out, "<section id=\"tree\">")?;
+        writeln!(out, "<h2>Directory Tree</h2>")?;
+        writeln!(out, "<pre class=\"tree\">.\n{}</pre>", html_escape(tree))?;
+        writeln!(out, "</section>")?;
+    }
+
+    let anchors: HashMap<PathBuf, usize> = matched_files
+        .iter()
+        .enumerate()
+        .map(|(index, path)| (output::canonical_or_fallback(path), index + 1))
+        .collect();
+
+    if let Some(metadata) = metadata {
+        write_metadata_table(out, config, metadata, &anchors)?;
+    }
+
+    writeln!(out, "<section id=\"files\">")?;
+    writeln!(out, "<h2>File Contents ({total} files)</h2>")?;
+
+    if matched_files.is_empty() {
+        writeln!(out, "<p>No files matched the criteria.</p>")?;
+    } else {
+        writeln!(
+            out,
+            "<p class=\"controls\"><button type=\"button\" onclick=\"setAllFiles(true)\">Expand all</button><button type=\"button\" onclick=\"setAllFiles(false)\">Collapse all</button></p>"
+        )?;
+    }
+
+    for (index, file_path) in matched_files.iter().enumerate() {
+        write_file_section(out, config, file_path, index + 1)?;
+    }
+
+    writeln!(out, "</section>")?;
+    writeln!(out, "<script>{SCRIPT}</script>")?;
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")?;
+
+    out.flush()?;
+    Ok(())
+}
+
+fn write_metadata_table(
+    out: &mut dyn Write,
+    config: &RunConfig,
+    metadata: &[FileMetadata],
+    anchors: &HashMap<PathBuf, usize>,
+) -> Result<()> {
+    writeln!(out, "<section id=\"metadata\">")?;
+    writeln!(out, "<h2>File Metadata ({} files)</h2>", metadata.len())?;
+
+    if metadata.is_empty() {
+        writeln!(out, "<p>No files matched the criteria.</p>")?;
+        writeln!(out, "</section>")?;
+        return Ok(());
+    }
+
+    writeln!(out, "<table class=\"sortable\">")?;
+    writeln!(
+        out,
+        "<thead><tr><th data-type=\"number\">#</th><th>Path</th><th>Language</th><th data-type=\"number\">Lines</th><th data-type=\"number\">Characters</th></tr></thead>"
+    )?;
+    writeln!(out, "<tbody>")?;
+
+    for (position, entry) in metadata.iter().enumerate() {
+        let path = config.path_display.display(&entry.path);
+        let language = if entry.is_binary {
+            "binary"
+        } else {
+            language::language_for_path(&entry.path).unwrap_or_default()
+        };
+
+        let link = match anchors.get(&entry.path) {
+            Some(index) => format!("<a href=\"#file-{index}\">{}</a>", html_escape(&path)),
+            None => html_escape(&path),
+        };
+
+        let (lines, characters) = match &entry.read_error {
+            Some(error) => (
+                format!(
+                    "<td class=\"error\" data-value=\"0\">{}</td>",
+                    html_escape(error)
+                ),
+                "<td class=\"count\" data-value=\"0\"></td>".to_string(),
+            ),
+            None => (
+                match entry.cells {
+                    Some(cells) => {
+                        format!("<td class=\"count\" data-value=\"{cells}\">{cells} cells</td>")
+                    }
+                    None => format!(
+                        "<td class=\"count\" data-value=\"{0}\">{0}</td>",
+                        entry.lines
+                    ),
+                },
+                format!(
+                    "<td class=\"count\" data-value=\"{0}\">{0}</td>",
+                    entry.characters
+                ),
+            ),
+        };
+
+        writeln!(
+            out,
+            "<tr><td class=\"count\" data-value=\"{0}\">{0}</td><td class=\"path\" data-value=\"{1}\">{link}</td><td data-value=\"{2}\">{2}</td>{lines}{characters}</tr>",
+            position + 1,
+            html_escape(&path),
+            language,
+        )?;
+    }
+
+    writeln!(out, "</tbody>")?;
+    writeln!(out, "</table>")?;
+    writeln!(out, "</section>")?;
+    Ok(())
+}
+
+fn write_file_section(
+    out: &mut dyn Write,
+    config: &RunConfig,
+    file_path: &Path,
+    index: usize,
+) -> Result<()> {
+    let display_path = config.path_display.display(file_path);
+    let language = language::language_for_path(file_path);
+
+    writeln!(out, "<details class=\"file\" id=\"file-{index}\" open>")?;
+
+    match output::read_file_body(config, file_path) {
+        FileBody::Text(text) => {
+            // Outlined and truncated files keep their original line numbers;
+            // outlines are always numbered.
+            let excerpt = output::excerpt_text(config, file_path, &text);
+            let numbered = config.line_numbers || output::is_outlined(config, file_path);
+            let (text, numbers): (String, Vec<Option<usize>>) = match &excerpt {
+                Some(excerpt) => (
+                    excerpt.text(),
+                    excerpt.lines.iter().map(|line| line.number).collect(),
+                ),
+                None => (text, Vec::new()),
+            };
+            let lines = highlight_lines(
+                &text,
+                output::content_language(file_path).and_then(syntax::syntax_for),
+            );
+            writeln!(
+                out,
+                "<summary><span class=\"path\">{}</span><span class=\"meta\">{} &middot; {} lines</span></summary>",
+                html_escape(&display_path),
+                language.unwrap_or("text"),
+                lines.len()
+            )?;
+
+            let width = excerpt
+                .as_ref()
+                .map_or(lines.len(), |excerpt| excerpt.original_lines)
+                .max(1)
+                .to_string()
+                .len();
+            write!(out, "<pre class=\"code\"><code>")?;
+            for (offset, line) in lines.iter().enumerate() {
+                if offset > 0 {
+                    writeln!(out)?;
+                }
+
+                if numbered {
+                    let number = match numbers.get(offset) {
+                        Some(number) => number.map(|number| number.to_string()),
+                        None => Some((offset + 1).to_string()),
+                    };
+                    write!(
+                        out,
+                        "<span class=\"ln\">{:>width$}</span>",
+                        number.unwrap_or_default()
+                    )?;
+                }
+
+                write!(out, "{line}")?;
+            }
+            writeln!(out, "</code></pre>")?;
+        }
+        FileBody::Binary(bytes) => {
+            let name = file_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+
+            writeln!(
+                out,
+                "<summary><span class=\"path\">{}</span><span class=\"meta\">binary &middot; {} bytes</span></summary>",
+                html_escape(&display_path),
+                bytes.len()
+            )?;
+            writeln!(
+                out,
+                "<pre><a class=\"download\" download=\"{0}\" href=\"data:{1};base64,{2}\">Download {0} ({3} bytes)</a></pre>",
+                html_escape(&name),
+                mime_type_for_path(file_path),
+                base64::engine::general_purpose::STANDARD.encode(&bytes),
+                bytes.len()
+            )?;
+        }
+        FileBody::Unreadable(error) => {
+            writeln!(
+                out,
+                "<summary><span class=\"path\">{}</span><span class=\"meta\">unreadable</span></summary>",
+                html_escape(&display_path)
+            )?;
+            writeln!(
+                out,
+                "<pre class=\"error\">Error reading file: {}</pre>",
+                html_escape(&error)
+            )?;
+        }
+    }
+
+    writeln!(out, "</details>")?;
+    Ok(())
+}
+
+fn highlight_lines(text: &str, syntax: Option<&Syntax>) -> Vec<String> {
+    let mut lines = vec![String::new()];
+
+    match syntax {
+        Some(syntax) => {
+            for token in syntax::tokenize(text, syntax) {
+                match token.kind {
+                    TokenKind::Code => push_code(&mut lines, token.text, syntax),
+                    TokenKind::Comment | TokenKind::DocComment => {
+                        push_span(&mut lines, Some("com"), token.text)
+                    }
+                    TokenKind::Str => push_span(&mut lines, Some("str"), token.text),
+                }
+            }
+        }
+        None => push_span(&mut lines, None, text),
+    }
+
+    if lines.len() > 1 && lines.last().is_some_and(String::is_empty) {
+        lines.pop();
+    }
+
+    lines
+}
+
+fn push_span(lines: &mut Vec<String>, class: Option<&str>, text: &str) {
+    for (index, segment) in text.split('\n').enumerate() {
+        if index > 0 {
+            lines.push(String::new());
+        }
+
+        if segment.is_empty() {
+            continue;
+        }
+
+        let line = lines.last_mut().expect("at least one line");
+        match class {
+            Some(class) => {
+                line.push_str(&format!(
+                    "<span class=\"{class}\">{}</span>",
+                    html_escape(segment)
+                ));
+            }
+            None => line.push_str(&html_escape(segment)),
+        }
+    }
+}
+
+fn push_code(lines: &mut Vec<String>, text: &str, syntax: &Syntax) {
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let word_len = rest
+            .find(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+            .unwrap_or(rest.len());
+
+        if word_len == 0 {
+            let ch_len = rest.chars().next().map_or(1, char::len_utf8);
+            push_span(lines, None, &rest[..ch_len]);
+            rest = &rest[ch_len..];
+            continue;
+        }
+
+        let word = &rest[..word_len];
+        let class = if syntax.keywords.contains(&word)
+            || syntax
+                .keywords
+                .contains(&word.to_ascii_uppercase().as_str())
+        {
+            Some("kw")
+        } else if word.starts_with(|ch: char| ch.is_ascii_digit()) {
+            Some("num")
+        } else {
+            None
+        };
+
+        push_span(lines, class, word);
+        rest = &rest[word_len..];
+    }
+}
+
+fn mime_type_for_path(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        _ => "application/octet-stream",
+    }
+}
+
+fn html_escape(input: &str) -> String {
+    input
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
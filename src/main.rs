@@ -1,17 +1,43 @@
+mod apply;
+mod archive;
+mod budget;
+mod bundle;
 mod clean;
 mod cleanup;
 mod cli;
 mod clipboard;
+mod comments;
+mod compress;
 mod config;
+mod diff;
 mod discover;
+mod file_attrs;
 mod filter;
+mod headers;
+mod html;
 mod inputs;
+mod language;
+mod manifest;
+mod minify;
+mod notebook;
+mod outline;
 mod output;
 mod output_name;
+mod paths;
+mod prompt;
+mod redact;
+mod rerun;
 mod run;
 mod sort;
+mod split;
+mod syntax;
+mod template;
 mod text_detect;
+mod tokens;
 mod tree;
+mod truncate;
+mod unpack;
+mod validate;
 
 use anyhow::Result;
 use clap::Parser;
@@ -23,9 +49,13 @@ fn main() -> Result<()> {
 
     match cli.command {
         Some(cli::Command::Clean(clean_args)) => clean::run(clean_args),
+        Some(cli::Command::Validate(validate_args)) => validate::run(validate_args),
+        Some(cli::Command::Unpack(unpack_args)) => unpack::run(unpack_args),
+        Some(cli::Command::Apply(apply_args)) => apply::run(apply_args),
+        Some(cli::Command::Diff(diff_args)) => diff::run(diff_args),
+        Some(cli::Command::Rerun(rerun_args)) => rerun::run(rerun_args),
         None => {
-            let had_user_args = raw_args.len() > 1;
-            let config = config::RunConfig::from_cli(cli, had_user_args)?;
+            let config = config::RunConfig::from_cli(cli, &raw_args)?;
             config.print_summary();
 
             run::run(config)
@@ -0,0 +1,208 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, bail};
+use roxmltree::Document;
+
+use crate::cli::PathMode;
+use crate::config::RunConfig;
+use crate::file_attrs;
+use crate::template;
+
+pub struct Manifest {
+    pub version: &'static str,
+    pub timestamp: String,
+    pub cwd: String,
+    pub arguments: Vec<String>,
+    pub inputs: Vec<String>,
+    pub settings: Vec<(&'static str, String)>,
+    pub git: Option<GitState>,
+}
+
+pub struct GitState {
+    pub head: String,
+    pub dirty: bool,
+}
+
+pub struct RecordedRun {
+    pub version: Option<String>,
+    pub cwd: String,
+    pub arguments: Vec<String>,
+}
+
+pub fn collect(config: &RunConfig) -> Result<Manifest> {
+    let cwd = std::env::current_dir().context("Failed to get current working directory")?;
+
+    // `--paths anon` hides the project root and home directory everywhere,
+    // including here.
+    let cwd_label = if config.path_display.mode() == PathMode::Anon {
+        config.path_display.display(&cwd)
+    } else {
+        cwd.display().to_string()
+    };
+
+    Ok(Manifest {
+        version: env!("CARGO_PKG_VERSION"),
+        timestamp: file_attrs::format_timestamp(std::time::SystemTime::now()),
+        cwd: cwd_label,
+        arguments: config.arguments.clone(),
+        inputs: config.inputs.clone(),
+        settings: config.summary(),
+        git: git_state(&cwd),
+    })
+}
+
+fn git_state(cwd: &Path) -> Option<GitState> {
+    let head = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(cwd)
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    let status = Command::new("git")
+        .args(["status", "--porcelain"])
+        .current_dir(cwd)
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    Some(GitState {
+        head: String::from_utf8_lossy(&head.stdout).trim().to_string(),
+        dirty: !status.stdout.is_empty(),
+    })
+}
+
+pub fn set_context(ctx: &mut template::Context, manifest: Option<&Manifest>) -> Result<()> {
+    ctx.set("manifest", manifest.is_some());
+    let Some(manifest) = manifest else {
+        return Ok(());
+    };
+
+    let values = |values: &[String]| {
+        values
+            .iter()
+            .map(|value| {
+                let mut value_ctx = template::Context::new();
+                value_ctx.set("value", value.as_str());
+                value_ctx
+            })
+            .collect::<Vec<_>>()
+    };
+
+    ctx.set("manifest_version", manifest.version);
+    ctx.set("manifest_timestamp", manifest.timestamp.as_str());
+    ctx.set("manifest_cwd", manifest.cwd.as_str());
+    ctx.set("manifest_argument_count", manifest.arguments.len());
+    ctx.set("manifest_arguments", values(&manifest.arguments));
+    ctx.set(
+        "manifest_arguments_json",
+        serde_json::to_string(&manifest.arguments)?,
+    );
+    ctx.set("manifest_input_count", manifest.inputs.len());
+    ctx.set("manifest_inputs", values(&manifest.inputs));
+    ctx.set(
+        "manifest_settings",
+        manifest
+            .settings
+            .iter()
+            .map(|(name, value)| {
+                let mut setting_ctx = template::Context::new();
+                setting_ctx.set("name", *name);
+                setting_ctx.set("value", value.as_str());
+                setting_ctx
+            })
+            .collect::<Vec<_>>(),
+    );
+    ctx.set("manifest_git", manifest.git.is_some());
+    if let Some(git) = &manifest.git {
+        ctx.set("manifest_git_head", git.head.as_str());
+        ctx.set("manifest_git_dirty", git.dirty);
+    }
+
+    Ok(())
+}
+
+pub fn parse_recorded_run(source: &str) -> Result<RecordedRun> {
+    let trimmed = source.trim_start_matches('\u{feff}').trim_start();
+
+    if trimmed.starts_with("<?xml") || trimmed.starts_with("<concatenation") {
+        parse_xml_manifest(source)
+    } else {
+        parse_text_manifest(source)
+    }
+}
+
+fn parse_xml_manifest(source: &str) -> Result<RecordedRun> {
+    let doc = Document::parse(source).context("Bundle is not well-formed XML")?;
+    let Some(manifest) = doc
+        .root_element()
+        .children()
+        .find(|node| node.has_tag_name("manifest"))
+    else {
+        bail!("Bundle has no <manifest>; it was not written with --manifest.");
+    };
+
+    let text = |name: &str| {
+        manifest
+            .children()
+            .find(|node| node.has_tag_name(name))
+            .map(|node| node.text().unwrap_or_default().to_string())
+    };
+
+    let Some(cwd) = text("cwd") else {
+        bail!("The <manifest> has no <cwd>.");
+    };
+
+    let arguments = manifest
+        .children()
+        .find(|node| node.has_tag_name("arguments"))
+        .map(|node| {
+            node.children()
+                .filter(|child| child.has_tag_name("arg"))
+                .map(|child| child.text().unwrap_or_default().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(RecordedRun {
+        version: text("version"),
+        cwd,
+        arguments,
+    })
+}
+
+fn parse_text_manifest(source: &str) -> Result<RecordedRun> {
+    let mut lines = source.lines().skip_while(|line| *line != "# Manifest");
+    if lines.next().is_none() {
+        bail!("Bundle has no # Manifest section; it was not written with --manifest.");
+    }
+
+    let mut version = None;
+    let mut cwd = None;
+    let mut arguments = None;
+
+    for line in lines.take_while(|line| !line.is_empty()) {
+        if let Some(value) = line.strip_prefix("Version: ") {
+            version = Some(value.to_string());
+        } else if let Some(value) = line.strip_prefix("Cwd: ") {
+            cwd = Some(value.to_string());
+        } else if let Some(value) = line.strip_prefix("Arguments: ") {
+            arguments = Some(
+                serde_json::from_str(value).context("The manifest's Arguments line is invalid")?,
+            );
+        }
+    }
+
+    let (Some(cwd), Some(arguments)) = (cwd, arguments) else {
+        bail!("The # Manifest section is missing its Cwd or Arguments line.");
+    };
+
+    Ok(RecordedRun {
+        version,
+        cwd,
+        arguments,
+    })
+}
@@ -0,0 +1,270 @@
+use crate::comments;
+use crate::syntax::{self, Token, TokenKind};
+
+const INDENT_SENSITIVE: &[&str] = &["python", "yaml", "make", "haskell", "markdown"];
+
+const CSS_PUNCTUATION: &str = "{};,>";
+
+// Levels 2 and 3 only apply to known languages that are not indentation-sensitive.
+pub fn minify(text: &str, language: Option<&str>, level: u8) -> Option<String> {
+    let reindent =
+        level >= 2 && language.is_some_and(|language| !INDENT_SENSITIVE.contains(&language));
+
+    let minified = match (level, language) {
+        (3, Some("json")) => minify_json(text),
+        (3, Some("xml")) => minify_xml(text),
+        (3, Some("css")) => minify_css(text),
+        _ if reindent => tabify(&collapse_blank_lines(text, language), language),
+        _ => collapse_blank_lines(text, language),
+    };
+
+    (minified != text).then_some(minified)
+}
+
+fn collapse_blank_lines(text: &str, language: Option<&str>) -> String {
+    let strings = multiline_strings(text, language);
+    let mut out = String::with_capacity(text.len());
+    let mut previous_blank = false;
+    let mut offset = 0;
+
+    for line in text.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+
+        if inside_string(&strings, offset - 1) {
+            out.push_str(line);
+            previous_blank = false;
+            continue;
+        }
+
+        let (body, ending) = split_line_ending(line);
+        let body = body.trim_end_matches([' ', '\t']);
+
+        let blank = body.is_empty() && !inside_string(&strings, line_start);
+        if blank && previous_blank {
+            continue;
+        }
+        previous_blank = blank;
+
+        out.push_str(body);
+        out.push_str(ending);
+    }
+
+    out
+}
+
+// The indentation step is the most common increase between consecutive lines.
+fn tabify(text: &str, language: Option<&str>) -> String {
+    let strings = multiline_strings(text, language);
+    let lines: Vec<(&str, bool)> = text
+        .split_inclusive('\n')
+        .scan(0, |offset, line| {
+            let in_string = inside_string(&strings, *offset);
+            *offset += line.len();
+            Some((line, in_string))
+        })
+        .collect();
+
+    let Some(step) = indentation_step(&lines) else {
+        return text.to_string();
+    };
+
+    let mut out = String::with_capacity(text.len());
+
+    for (line, in_string) in lines {
+        let spaces = leading_spaces(line);
+        if spaces == 0 || in_string || line[spaces..].starts_with('\t') {
+            out.push_str(line);
+            continue;
+        }
+
+        out.push_str(&"\t".repeat(spaces / step));
+        out.push_str(&" ".repeat(spaces % step));
+        out.push_str(&line[spaces..]);
+    }
+
+    out
+}
+
+fn indentation_step(lines: &[(&str, bool)]) -> Option<usize> {
+    let mut counts = std::collections::BTreeMap::new();
+    let mut previous = 0;
+
+    for (line, _) in lines
+        .iter()
+        .filter(|(line, in_string)| !in_string && !line.trim().is_empty())
+    {
+        let spaces = leading_spaces(line);
+        if spaces > previous {
+            *counts.entry(spaces - previous).or_insert(0) += 1;
+        }
+        previous = spaces;
+    }
+
+    counts
+        .into_iter()
+        .max_by(|(step_a, count_a), (step_b, count_b)| {
+            count_a.cmp(count_b).then(step_b.cmp(step_a))
+        })
+        .map(|(step, _)| step)
+}
+
+fn multiline_strings(text: &str, language: Option<&str>) -> Vec<(usize, usize)> {
+    let Some(syntax) = language.and_then(syntax::syntax_for) else {
+        return Vec::new();
+    };
+
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    for token in syntax::tokenize(text, syntax) {
+        let end = start + token.text.len();
+        if token.kind == TokenKind::Str && token.text.contains('\n') {
+            ranges.push((start, end));
+        }
+        start = end;
+    }
+
+    ranges
+}
+
+fn inside_string(strings: &[(usize, usize)], offset: usize) -> bool {
+    strings
+        .iter()
+        .any(|&(start, end)| start < offset && offset < end)
+}
+
+fn minify_json(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in text.chars() {
+        if in_string {
+            out.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+            out.push(c);
+        } else if !c.is_whitespace() {
+            out.push(c);
+        }
+    }
+
+    keep_final_newline(text, out)
+}
+
+fn minify_xml(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+
+    for token in comments::tokenize_markup(text) {
+        match token.kind {
+            TokenKind::Code if token.text.trim().is_empty() => {}
+            TokenKind::Str
+                if token.text.starts_with('<') && !token.text.starts_with("<![CDATA[") =>
+            {
+                collapse_tag(&mut out, token.text);
+            }
+            _ => out.push_str(token.text),
+        }
+    }
+
+    keep_final_newline(text, out)
+}
+
+fn collapse_tag(out: &mut String, tag: &str) {
+    let mut quote = None;
+    let mut pending_space = false;
+
+    for c in tag.chars() {
+        match quote {
+            Some(open) => {
+                out.push(c);
+                if c == open {
+                    quote = None;
+                }
+            }
+            None if c.is_whitespace() => pending_space = true,
+            None => {
+                if pending_space && c != '>' {
+                    out.push(' ');
+                }
+                pending_space = false;
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                }
+                out.push(c);
+            }
+        }
+    }
+}
+
+fn minify_css(text: &str) -> String {
+    let Some(syntax) = syntax::syntax_for("css") else {
+        return text.to_string();
+    };
+
+    let mut out = String::with_capacity(text.len());
+    let mut pending_space = false;
+
+    for Token { kind, text: chunk } in syntax::tokenize(text, syntax) {
+        if kind != TokenKind::Code {
+            push_css(&mut out, &mut pending_space, chunk, false);
+            continue;
+        }
+
+        for c in chunk.chars() {
+            if c.is_whitespace() {
+                pending_space = true;
+            } else {
+                push_css(
+                    &mut out,
+                    &mut pending_space,
+                    c.encode_utf8(&mut [0; 4]),
+                    CSS_PUNCTUATION.contains(c),
+                );
+            }
+        }
+    }
+
+    keep_final_newline(text, out)
+}
+
+fn push_css(out: &mut String, pending_space: &mut bool, chunk: &str, punctuation: bool) {
+    let after_punctuation = out
+        .chars()
+        .last()
+        .is_none_or(|last| CSS_PUNCTUATION.contains(last));
+
+    if *pending_space && !punctuation && !after_punctuation {
+        out.push(' ');
+    }
+    *pending_space = false;
+    out.push_str(chunk);
+}
+
+fn keep_final_newline(original: &str, mut minified: String) -> String {
+    if original.ends_with('\n') && !minified.ends_with('\n') {
+        minified.push('\n');
+    }
+    minified
+}
+
+fn split_line_ending(line: &str) -> (&str, &str) {
+    if let Some(body) = line.strip_suffix("\r\n") {
+        (body, "\r\n")
+    } else if let Some(body) = line.strip_suffix('\n') {
+        (body, "\n")
+    } else {
+        (line, "")
+    }
+}
+
+fn leading_spaces(line: &str) -> usize {
+    line.bytes().take_while(|&byte| byte == b' ').count()
+}
@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+pub struct Notebook {
+    pub text: String,
+    pub cells: usize,
+}
+
+pub fn is_notebook(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ipynb"))
+}
+
+pub fn render(text: &str, outputs: bool) -> Option<Notebook> {
+    let notebook: Value = serde_json::from_str(text).ok()?;
+    let cells = notebook.get("cells")?.as_array()?;
+    let language = kernel_language(&notebook);
+
+    let mut out = String::with_capacity(text.len() / 2);
+    for (index, cell) in cells.iter().enumerate() {
+        let kind = cell
+            .get("cell_type")
+            .and_then(Value::as_str)
+            .unwrap_or("raw");
+        let source = joined(cell.get("source"));
+
+        if index > 0 {
+            out.push('\n');
+        }
+        out.push_str(&format!("<!-- cell {}: {kind} -->\n", index + 1));
+
+        match kind {
+            "markdown" => push_line(&mut out, &source),
+            "code" => {
+                push_fenced(&mut out, language, &source);
+
+                let printed = if outputs {
+                    cell_outputs(cell)
+                } else {
+                    String::new()
+                };
+                if !printed.is_empty() {
+                    out.push('\n');
+                    push_fenced(&mut out, "output", &printed);
+                }
+            }
+            _ => push_fenced(&mut out, "", &source),
+        }
+    }
+
+    Some(Notebook {
+        text: out,
+        cells: cells.len(),
+    })
+}
+
+fn kernel_language(notebook: &Value) -> &str {
+    let metadata = notebook.get("metadata");
+    metadata
+        .and_then(|metadata| metadata.get("kernelspec"))
+        .and_then(|kernelspec| kernelspec.get("language"))
+        .or_else(|| {
+            metadata
+                .and_then(|metadata| metadata.get("language_info"))
+                .and_then(|info| info.get("name"))
+        })
+        .and_then(Value::as_str)
+        .unwrap_or("python")
+}
+
+// Tracebacks carry terminal colour codes and are left out.
+fn cell_outputs(cell: &Value) -> String {
+    let Some(outputs) = cell.get("outputs").and_then(Value::as_array) else {
+        return String::new();
+    };
+
+    let mut printed = String::new();
+    for output in outputs {
+        let text = match output.get("output_type").and_then(Value::as_str) {
+            Some("stream") => joined(output.get("text")),
+            Some("execute_result" | "display_data") => match output.get("data") {
+                Some(data) if !has_image(data) => joined(data.get("text/plain")),
+                _ => String::new(),
+            },
+            Some("error") => {
+                let field = |name| output.get(name).and_then(Value::as_str).unwrap_or_default();
+                format!("{}: {}", field("ename"), field("evalue"))
+            }
+            _ => String::new(),
+        };
+
+        if !text.is_empty() {
+            push_line(&mut printed, &text);
+        }
+    }
+
+    printed
+}
+
+fn has_image(data: &Value) -> bool {
+    data.as_object()
+        .is_some_and(|data| data.keys().any(|mime| mime.starts_with("image/")))
+}
+
+fn joined(value: Option<&Value>) -> String {
+    match value {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(lines)) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+fn push_line(out: &mut String, text: &str) {
+    out.push_str(text);
+    if !text.is_empty() && !text.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+fn push_fenced(out: &mut String, info: &str, text: &str) {
+    let longest = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest.max(2) + 1);
+
+    out.push_str(&format!("{fence}{info}\n"));
+    push_line(out, text);
+    out.push_str(&format!("{fence}\n"));
+}
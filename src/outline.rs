@@ -0,0 +1,635 @@
+use std::path::Path;
+
+use anyhow::Result;
+use globset::{Glob, GlobMatcher};
+
+use crate::language;
+use crate::paths::PathDisplay;
+use crate::syntax::{self, TokenKind};
+use crate::truncate::{EmittedLine, Excerpt};
+
+#[derive(Debug)]
+pub struct Outline {
+    full: Vec<(String, GlobMatcher)>,
+}
+
+impl Outline {
+    pub fn new(full: &[String]) -> Result<Self> {
+        let full = full
+            .iter()
+            .map(|pattern| Ok((pattern.clone(), Glob::new(pattern)?.compile_matcher())))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { full })
+    }
+
+    pub fn describe(&self) -> String {
+        if self.full.is_empty() {
+            return "true".to_string();
+        }
+
+        let patterns: Vec<&str> = self
+            .full
+            .iter()
+            .map(|(pattern, _)| pattern.as_str())
+            .collect();
+        format!("true (full: {})", patterns.join(" "))
+    }
+
+    pub fn covers(&self, paths: &PathDisplay, path: &Path) -> bool {
+        if language::language_for_path(path)
+            .and_then(Lang::for_language)
+            .is_none()
+        {
+            return false;
+        }
+
+        let relative = paths.relative_to_cwd(path);
+        let name = path.file_name().unwrap_or_default();
+        !self
+            .full
+            .iter()
+            .any(|(_, matcher)| matcher.is_match(&relative) || matcher.is_match(name))
+    }
+
+    pub fn apply(&self, paths: &PathDisplay, path: &Path, text: &str) -> Option<Excerpt> {
+        if !self.covers(paths, path) {
+            return None;
+        }
+
+        let language = language::language_for_path(path)?;
+        let lang = Lang::for_language(language)?;
+        let views = Views::new(text, language);
+
+        let lines = match lang {
+            Lang::Python => outline_python(&views),
+            _ => outline_braces(&views, lang),
+        };
+
+        Some(Excerpt {
+            lines,
+            original_lines: text.lines().count(),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Lang {
+    Rust,
+    Go,
+    Java,
+    Script,
+    Python,
+}
+
+impl Lang {
+    fn for_language(language: &str) -> Option<Self> {
+        match language {
+            "rust" => Some(Self::Rust),
+            "go" => Some(Self::Go),
+            "java" => Some(Self::Java),
+            "javascript" | "jsx" | "typescript" | "tsx" => Some(Self::Script),
+            "python" => Some(Self::Python),
+            _ => None,
+        }
+    }
+
+    fn modifiers(self) -> &'static [&'static str] {
+        match self {
+            Self::Rust => &["pub", "async", "unsafe", "default", "extern", "const"],
+            Self::Java => &[
+                "public",
+                "private",
+                "protected",
+                "static",
+                "final",
+                "abstract",
+                "sealed",
+                "non-sealed",
+                "synchronized",
+                "native",
+                "default",
+                "strictfp",
+            ],
+            Self::Script => &[
+                "export",
+                "default",
+                "declare",
+                "async",
+                "abstract",
+                "public",
+                "private",
+                "protected",
+                "static",
+                "readonly",
+                "override",
+            ],
+            Self::Go | Self::Python => &[],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Item {
+    Container,
+    Body,
+    Line,
+    Skip,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum End {
+    Brace,
+    Statement,
+}
+
+// `structure` also blanks string literals, so their braces are never counted.
+struct Views<'a> {
+    text: &'a str,
+    display: String,
+    structure: String,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> Views<'a> {
+    fn new(text: &'a str, language: &str) -> Self {
+        let mut display = String::with_capacity(text.len());
+        let mut structure = String::with_capacity(text.len());
+
+        match syntax::syntax_for(language) {
+            Some(syntax) => {
+                for token in syntax::tokenize(text, syntax) {
+                    match token.kind {
+                        TokenKind::Code => {
+                            display.push_str(token.text);
+                            structure.push_str(token.text);
+                        }
+                        TokenKind::Str => {
+                            display.push_str(token.text);
+                            structure.push_str(&blank(token.text));
+                        }
+                        TokenKind::Comment | TokenKind::DocComment => {
+                            display.push_str(&blank(token.text));
+                            structure.push_str(&blank(token.text));
+                        }
+                    }
+                }
+            }
+            None => {
+                display.push_str(text);
+                structure.push_str(text);
+            }
+        }
+
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(offset, _)| offset + 1))
+            .collect();
+
+        Self {
+            text,
+            display,
+            structure,
+            line_starts,
+        }
+    }
+
+    fn line_number(&self, offset: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= offset)
+    }
+
+    fn indentation(&self, offset: usize) -> &str {
+        let start = self.line_starts[self.line_number(offset) - 1];
+        let line = &self.text[start..];
+        &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+    }
+
+    fn emit(&self, lines: &mut Vec<EmittedLine>, offset: usize, text: &str) {
+        lines.push(EmittedLine {
+            number: Some(self.line_number(offset)),
+            text: format!("{}{text}\n", self.indentation(offset)),
+        });
+    }
+}
+
+fn blank(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c == '\n' {
+                "\n".to_string()
+            } else {
+                " ".repeat(c.len_utf8())
+            }
+        })
+        .collect()
+}
+
+enum Frame {
+    Container { header: usize },
+    Hidden,
+}
+
+fn outline_braces(views: &Views, lang: Lang) -> Vec<EmittedLine> {
+    let bytes = views.structure.as_bytes();
+    let mut lines = Vec::new();
+    let mut frames: Vec<Frame> = Vec::new();
+    let mut hidden_depth = 0;
+    let mut nesting = 0usize;
+    let mut start = 0;
+
+    for (pos, &byte) in bytes.iter().enumerate() {
+        if hidden_depth > 0 {
+            match byte {
+                b'{' => hidden_depth += 1,
+                b'}' => {
+                    hidden_depth -= 1;
+                    if hidden_depth == 0 {
+                        frames.pop();
+                        start = pos + 1;
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let in_class = matches!(frames.last(), Some(Frame::Container { .. }));
+        match byte {
+            b'(' | b'[' => nesting += 1,
+            b')' | b']' => nesting = nesting.saturating_sub(1),
+            b'{' if nesting == 0 => {
+                let item =
+                    show_statement(views, lang, &mut lines, start, pos, End::Brace, in_class);
+                if item == Item::Container {
+                    frames.push(Frame::Container {
+                        header: lines.len() - 1,
+                    });
+                } else {
+                    frames.push(Frame::Hidden);
+                    hidden_depth = 1;
+                }
+                start = pos + 1;
+            }
+            b'}' if nesting == 0 => {
+                if let Some(Frame::Container { header }) = frames.pop() {
+                    close_container(views, &mut lines, header, pos);
+                }
+                start = pos + 1;
+            }
+            b';' if nesting == 0 => {
+                show_statement(
+                    views,
+                    lang,
+                    &mut lines,
+                    start,
+                    pos + 1,
+                    End::Statement,
+                    in_class,
+                );
+                start = pos + 1;
+            }
+            b'\n' if nesting == 0 && lang == Lang::Go => {
+                show_statement(
+                    views,
+                    lang,
+                    &mut lines,
+                    start,
+                    pos,
+                    End::Statement,
+                    in_class,
+                );
+                start = pos + 1;
+            }
+            _ => {}
+        }
+    }
+
+    lines
+}
+
+fn close_container(views: &Views, lines: &mut Vec<EmittedLine>, header: usize, pos: usize) {
+    if lines[header].number != Some(views.line_number(pos)) {
+        views.emit(lines, pos, "}");
+        return;
+    }
+
+    lines.truncate(header + 1);
+    let folded = lines[header]
+        .text
+        .strip_suffix("{\n")
+        .map(|text| format!("{text}{{ … }}\n"));
+    if let Some(folded) = folded {
+        lines[header].text = folded;
+    }
+}
+
+// A statement can span lines that are not part of the declaration, such as attributes.
+fn show_statement(
+    views: &Views,
+    lang: Lang,
+    lines: &mut Vec<EmittedLine>,
+    start: usize,
+    end: usize,
+    ending: End,
+    in_class: bool,
+) -> Item {
+    let structure = &views.structure[start..end];
+    let line_offsets = std::iter::once(0).chain(
+        structure
+            .match_indices('\n')
+            .map(|(offset, _)| offset + 1)
+            .filter(|&offset| offset < structure.len()),
+    );
+    let candidates: Vec<usize> = line_offsets.collect();
+
+    for &offset in candidates.iter().rev() {
+        let header_start = start + offset + attribute_len(&structure[offset..], lang);
+        let header = views.structure[header_start..end].trim();
+        if header.is_empty() {
+            continue;
+        }
+
+        let item = classify(header, lang, ending, in_class);
+        if item == Item::Skip {
+            continue;
+        }
+
+        let first = header_start
+            + (views.structure[header_start..end].len()
+                - views.structure[header_start..end].trim_start().len());
+        let signature = collapse(&views.display[first..end]);
+        let signature = signature.trim_end_matches(',');
+        let shown = match item {
+            Item::Container => format!("{signature} {{"),
+            Item::Body => format!("{signature} {{ … }}"),
+            _ => shorten_arrow(signature),
+        };
+        views.emit(lines, first, &shown);
+        return item;
+    }
+
+    Item::Skip
+}
+
+fn attribute_len(text: &str, lang: Lang) -> usize {
+    let mut pos = 0;
+
+    loop {
+        let rest = &text[pos..];
+        let trimmed = rest.trim_start();
+        let skipped = rest.len() - trimmed.len();
+
+        let attribute = match lang {
+            Lang::Rust if trimmed.starts_with("#[") || trimmed.starts_with("#![") => {
+                balanced_len(trimmed, trimmed.find('[').unwrap_or(0))
+            }
+            Lang::Java | Lang::Script
+                if trimmed.starts_with('@') && !trimmed.starts_with("@interface") =>
+            {
+                let name = 1 + trimmed[1..]
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+                    .unwrap_or(trimmed.len() - 1);
+                if trimmed[name..].starts_with('(') {
+                    balanced_len(trimmed, name)
+                } else {
+                    Some(name)
+                }
+            }
+            _ => None,
+        };
+
+        match attribute {
+            Some(len) if len > 0 => pos += skipped + len,
+            _ => return pos,
+        }
+    }
+}
+
+fn balanced_len(text: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (offset, byte) in text.bytes().enumerate().skip(open) {
+        match byte {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(offset + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn classify(header: &str, lang: Lang, ending: End, in_class: bool) -> Item {
+    let mut words = header.split_whitespace().peekable();
+    let mut stripped_extern = false;
+
+    while let Some(&word) = words.peek() {
+        let modifier = lang.modifiers().contains(&word)
+            || (lang == Lang::Rust && word.starts_with("pub("))
+            || (lang == Lang::Rust && stripped_extern && word.starts_with('"'));
+        if !modifier {
+            break;
+        }
+        stripped_extern |= word == "extern";
+        words.next();
+    }
+
+    let Some(keyword) = words.next() else {
+        // `extern "C" { … }` declares foreign items.
+        return if stripped_extern && ending == End::Brace {
+            Item::Container
+        } else {
+            Item::Skip
+        };
+    };
+
+    // `impl<T>` and `function<T>` carry their generics on the keyword.
+    let keyword = match keyword.find('<') {
+        Some(end) if end > 0 => &keyword[..end],
+        _ => keyword,
+    };
+
+    let body_or_line = if ending == End::Brace {
+        Item::Body
+    } else {
+        Item::Line
+    };
+
+    match lang {
+        Lang::Rust => match keyword {
+            "impl" | "trait" if ending == End::Brace => Item::Container,
+            "mod" if ending == End::Brace => Item::Container,
+            "mod" | "fn" | "struct" | "enum" | "union" | "trait" => body_or_line,
+            "type" if ending == End::Statement => Item::Line,
+            "macro_rules!" => Item::Body,
+            _ => Item::Skip,
+        },
+        Lang::Go => match keyword {
+            "func" | "type" => body_or_line,
+            "package" if ending == End::Statement => Item::Line,
+            _ => Item::Skip,
+        },
+        Lang::Java => match keyword {
+            "class" | "interface" | "enum" | "record" | "@interface" if ending == End::Brace => {
+                Item::Container
+            }
+            "package" if ending == End::Statement => Item::Line,
+            _ if in_class && is_method(header, keyword) => body_or_line,
+            _ => Item::Skip,
+        },
+        Lang::Script => match keyword {
+            "class" if ending == End::Brace => Item::Container,
+            "namespace" | "module" if ending == End::Brace => Item::Container,
+            "function" | "function*" | "interface" | "enum" | "type" => body_or_line,
+            "const" | "let" | "var" if is_function_value(header) => body_or_line,
+            _ if in_class && (is_method(header, keyword) || is_function_value(header)) => {
+                body_or_line
+            }
+            _ => Item::Skip,
+        },
+        Lang::Python => Item::Skip,
+    }
+}
+
+const CONTROL_WORDS: &[&str] = &[
+    "if",
+    "else",
+    "for",
+    "while",
+    "do",
+    "switch",
+    "case",
+    "return",
+    "new",
+    "throw",
+    "try",
+    "catch",
+    "finally",
+    "synchronized",
+    "await",
+    "yield",
+    "super",
+    "this",
+];
+
+fn is_method(header: &str, keyword: &str) -> bool {
+    let Some(paren) = header.find('(') else {
+        return false;
+    };
+
+    !CONTROL_WORDS.contains(&keyword.trim_end_matches('('))
+        && !header[..paren].contains('=')
+        && !header[..paren].trim().is_empty()
+}
+
+fn is_function_value(header: &str) -> bool {
+    header.split_once('=').is_some_and(|(_, value)| {
+        let value = value.trim_start();
+        value.contains("=>")
+            || value.starts_with("function")
+            || value.starts_with("async function")
+            || value.starts_with("async (")
+    })
+}
+
+fn shorten_arrow(signature: &str) -> String {
+    match signature.find("=>") {
+        Some(arrow) => format!("{} …;", &signature[..arrow + 2]),
+        None => signature.to_string(),
+    }
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("( ", "(")
+        .replace(", )", ")")
+        .replace(",)", ")")
+        .replace(" )", ")")
+        .replace("< ", "<")
+        .replace(", >", ">")
+}
+
+fn outline_python(views: &Views) -> Vec<EmittedLine> {
+    let mut lines = Vec::new();
+    // Indentation of each open `class` or `def`, and whether it is a class.
+    let mut open: Vec<(usize, bool)> = Vec::new();
+    let mut offset = 0;
+    let structure_lines: Vec<&str> = views.structure.split_inclusive('\n').collect();
+    let mut index = 0;
+
+    while index < structure_lines.len() {
+        let line = structure_lines[index];
+        let line_start = offset;
+        offset += line.len();
+        index += 1;
+
+        let trimmed = line.trim_start();
+        if trimmed.trim().is_empty() {
+            continue;
+        }
+
+        let indent = line.len() - trimmed.len();
+        while open
+            .last()
+            .is_some_and(|&(open_indent, _)| indent <= open_indent)
+        {
+            open.pop();
+        }
+
+        let in_function = open.last().is_some_and(|&(_, is_class)| !is_class);
+        let is_class = trimmed.starts_with("class ");
+        let is_def = trimmed.starts_with("def ") || trimmed.starts_with("async def ");
+        if in_function || !(is_class || is_def) {
+            continue;
+        }
+
+        // The signature runs to the `:` that closes it, across lines.
+        let mut end = line_start + line.len();
+        let mut depth = paren_balance(line);
+        while depth > 0 && index < structure_lines.len() {
+            depth += paren_balance(structure_lines[index]);
+            end += structure_lines[index].len();
+            offset += structure_lines[index].len();
+            index += 1;
+        }
+
+        let first = line_start + indent;
+        let end =
+            signature_end(&views.structure[first..end]).map_or(end, |colon| first + colon + 1);
+        let signature = collapse(&views.display[first..end]);
+        let shown = if is_class {
+            signature
+        } else {
+            format!("{signature} …")
+        };
+        views.emit(&mut lines, first, &shown);
+        open.push((indent, is_class));
+    }
+
+    lines
+}
+
+fn signature_end(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (offset, byte) in text.bytes().enumerate() {
+        match byte {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth -= 1,
+            b':' if depth == 0 => return Some(offset),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn paren_balance(line: &str) -> isize {
+    line.bytes().fold(0, |depth, byte| match byte {
+        b'(' | b'[' | b'{' => depth + 1,
+        b')' | b']' | b'}' => depth - 1,
+        _ => depth,
+    })
+}
@@ -1,46 +1,147 @@
+use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use base64::Engine as _;
 
-use crate::cli::MetadataSort;
+use crate::budget::BudgetReport;
+use crate::cli::{FileAttr, MetadataSort, XmlSchema};
+use crate::comments;
+use crate::compress::Encoder;
 use crate::config::{OutputFormat, RunConfig};
+use crate::file_attrs;
+use crate::language;
+use crate::minify;
+use crate::notebook::{self, Notebook};
+use crate::paths::PathDisplay;
 use crate::sort;
+use crate::split::{FileChunk, Part, PartEntry};
+use crate::template::{self, Template};
 use crate::text_detect;
+use crate::truncate::Excerpt;
 
 #[derive(Debug, Clone)]
-struct FileMetadata {
-    path: PathBuf,
-    lines: usize,
-    characters: usize,
-    is_binary: bool,
-    read_error: Option<String>,
+pub struct FileMetadata {
+    pub path: PathBuf,
+    pub lines: usize,
+    pub cells: Option<usize>,
+    pub characters: usize,
+    pub emitted: Option<(usize, usize)>,
+    pub truncated: bool,
+    pub saved_bytes: usize,
+    pub secrets: BTreeMap<String, usize>,
+    pub tokens: usize,
+    pub is_binary: bool,
+    pub read_error: Option<String>,
+    pub attributes: Vec<(FileAttr, String)>,
+}
+
+pub struct PreparedText {
+    pub text: Option<String>,
+    pub cells: Option<usize>,
+    pub secrets: BTreeMap<String, usize>,
+    pub saved_bytes: usize,
+    // Rendered, stripped or minified rather than only redacted.
+    pub reshaped: bool,
+}
+
+pub enum FileBody {
+    Text(String),
+    Binary(Vec<u8>),
+    Unreadable(String),
 }
 
 pub fn write_output(
     config: &RunConfig,
     output_path: &Path,
     matched_files: &[PathBuf],
+    metadata: &[FileMetadata],
     tree: Option<&str>,
+    budget: Option<&BudgetReport>,
 ) -> Result<()> {
     let file = std::fs::File::create(output_path)?;
-    let mut out = BufWriter::new(file);
-    write_output_to_writer(config, matched_files, tree, &mut out)?;
-    out.flush()?;
+    let out = BufWriter::new(file);
+
+    if config.format == OutputFormat::Zip {
+        return crate::archive::write_archive(config, matched_files, metadata, out);
+    }
+
+    write_stream(config, matched_files, metadata, tree, budget, out)
+}
+
+pub fn write_output_to_stdout(
+    config: &RunConfig,
+    matched_files: &[PathBuf],
+    metadata: &[FileMetadata],
+    tree: Option<&str>,
+    budget: Option<&BudgetReport>,
+) -> Result<()> {
+    let result = if config.format == OutputFormat::Zip {
+        let mut archive = std::io::Cursor::new(Vec::new());
+        crate::archive::write_archive(config, matched_files, metadata, &mut archive).and_then(
+            |()| {
+                let mut stdout = std::io::stdout().lock();
+                stdout.write_all(archive.get_ref())?;
+                stdout.flush()?;
+                Ok(())
+            },
+        )
+    } else {
+        write_stream(
+            config,
+            matched_files,
+            metadata,
+            tree,
+            budget,
+            BufWriter::new(std::io::stdout().lock()),
+        )
+    };
+
+    match result {
+        Err(err)
+            if err
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|err| err.kind() == std::io::ErrorKind::BrokenPipe) =>
+        {
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+fn write_stream<W: Write>(
+    config: &RunConfig,
+    matched_files: &[PathBuf],
+    metadata: &[FileMetadata],
+    tree: Option<&str>,
+    budget: Option<&BudgetReport>,
+    out: W,
+) -> Result<()> {
+    let mut out = Encoder::new(config.compression, out)?;
+
+    if config.format == OutputFormat::Tar {
+        crate::archive::write_tar_archive(config, matched_files, metadata, &mut out)?;
+    } else {
+        write_output_to_writer(config, matched_files, metadata, tree, budget, &mut out)?;
+    }
+
+    out.finish()?.flush()?;
     Ok(())
 }
 
 pub fn render_output(
     config: &RunConfig,
     matched_files: &[PathBuf],
+    metadata: &[FileMetadata],
     tree: Option<&str>,
+    budget: Option<&BudgetReport>,
 ) -> Result<String> {
     let mut buffer = Vec::new();
     {
         let mut out = BufWriter::new(&mut buffer);
-        write_output_to_writer(config, matched_files, tree, &mut out)?;
+        write_output_to_writer(config, matched_files, metadata, tree, budget, &mut out)?;
         out.flush()?;
     }
 
@@ -50,111 +151,350 @@ pub fn render_output(
 fn write_output_to_writer(
     config: &RunConfig,
     matched_files: &[PathBuf],
+    metadata: &[FileMetadata],
     tree: Option<&str>,
+    budget: Option<&BudgetReport>,
     out: &mut dyn Write,
 ) -> Result<()> {
-    let metadata = if config.show_metadata {
-        Some(collect_file_metadata(matched_files, config.metadata_sort))
-    } else {
-        None
-    };
+    match (&config.template, config.format) {
+        (None, OutputFormat::Html) => crate::html::write_html_output(
+            config,
+            matched_files,
+            tree,
+            metadata_section(config, metadata).as_deref(),
+            out,
+        ),
+        (None, OutputFormat::Tar | OutputFormat::Zip) => bail!(
+            "--format {} can only be written to a file.",
+            config.format.as_str()
+        ),
+        _ => write_bundle(config, matched_files, metadata, tree, budget, out),
+    }
+}
 
-    match config.format {
-        OutputFormat::Xml => {
-            write_xml_output(config, matched_files, tree, metadata.as_deref(), out)
-        }
-        OutputFormat::Text => {
-            write_text_output(config, matched_files, tree, metadata.as_deref(), out)
-        }
+// With --split-size / --split-tokens a bundle is written as parts and an index.
+pub enum Document<'a> {
+    Whole,
+    Part { number: usize, count: usize },
+    Index(&'a [Part]),
+}
+
+fn layout(config: &RunConfig) -> &Template {
+    match &config.template {
+        Some(template) => template,
+        None if config.format == OutputFormat::Text => Template::builtin("text"),
+        None => Template::builtin("xml"),
     }
 }
 
-fn write_xml_output(
+fn write_bundle(
     config: &RunConfig,
     matched_files: &[PathBuf],
+    metadata: &[FileMetadata],
     tree: Option<&str>,
-    metadata: Option<&[FileMetadata]>,
+    budget: Option<&BudgetReport>,
     out: &mut dyn Write,
 ) -> Result<()> {
-    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
-    writeln!(out, "<concatenation>")?;
-
-    if let Some(tree) = tree {
-        writeln!(out, "  <directoryTree context=\".\">")?;
-        writeln!(out, "    <representation><![CDATA[")?;
-        write_cdata_body(out, tree)?;
-        writeln!(out, "]]></representation>")?;
-        writeln!(out, "  </directoryTree>")?;
-    }
+    let template = layout(config);
+    let total = matched_files.len();
 
-    if config.show_dir_list {
-        write_matched_dir_list_xml(out, matched_files)?;
-    }
+    let mut document = document_context(config, &Document::Whole, total, total);
+    set_overview_context(&mut document, config, matched_files, metadata, tree, budget)?;
+
+    template.render_header(&document, out)?;
 
-    if let Some(metadata) = metadata {
-        write_file_metadata_xml(out, metadata)?;
+    for (index, (file_path, entry)) in matched_files.iter().zip(metadata).enumerate() {
+        let ctx = file_context(config, file_path, entry, index + 1, total, None);
+        template.render_file(&ctx, out)?;
     }
 
-    writeln!(out, "  <fileContents count=\"{}\">", matched_files.len())?;
+    template.render_footer(&document, out)?;
 
-    if matched_files.is_empty() {
-        writeln!(out, "    <message>No files matched the criteria.</message>")?;
-    } else {
-        for file_path in matched_files {
-            let absolute_path = canonical_or_fallback(file_path);
-            let absolute_path_string = absolute_path.to_string_lossy();
-
-            writeln!(out, "    <file>")?;
-            writeln!(
-                out,
-                "      <path>{}</path>",
-                xml_escape_text(&absolute_path_string)
-            )?;
-
-            let bytes = std::fs::read(file_path);
-            let (content, encoding_attr) = match bytes {
-                Ok(bytes) => {
-                    if config.include_binary && !text_detect::bytes_are_probably_text(&bytes) {
-                        (
-                            base64::engine::general_purpose::STANDARD.encode(bytes),
-                            Some("base64"),
-                        )
-                    } else {
-                        (sanitize_xml_text(&String::from_utf8_lossy(&bytes)), None)
-                    }
-                }
-                Err(_) => (
-                    format!("Error reading file content for {}", file_path.display()),
-                    None,
-                ),
-            };
-
-            if let Some(encoding) = encoding_attr {
-                writeln!(out, "      <content encoding=\"{encoding}\"><![CDATA[")?;
-            } else {
-                writeln!(out, "      <content><![CDATA[")?;
-            }
+    out.flush()?;
+    Ok(())
+}
 
-            write_cdata_body(out, &content)?;
-            writeln!(out, "]]></content>")?;
-            writeln!(out, "    </file>")?;
-        }
+pub fn render_part_entry(
+    config: &RunConfig,
+    entry: &PartEntry,
+    total_files: usize,
+) -> Result<String> {
+    render_file_section(
+        config,
+        &entry.path,
+        &entry.metadata,
+        entry.index,
+        total_files,
+        entry.chunk.as_ref(),
+    )
+}
+
+pub fn render_file_section(
+    config: &RunConfig,
+    file_path: &Path,
+    entry: &FileMetadata,
+    index: usize,
+    total: usize,
+    chunk: Option<&FileChunk>,
+) -> Result<String> {
+    let ctx = file_context(config, file_path, entry, index, total, chunk);
+
+    let mut buffer = Vec::new();
+    layout(config).render_file(&ctx, &mut buffer)?;
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+pub fn write_part(
+    config: &RunConfig,
+    number: usize,
+    count: usize,
+    total_files: usize,
+    sections: &[String],
+    out: &mut dyn Write,
+) -> Result<()> {
+    let template = layout(config);
+    let document = document_context(
+        config,
+        &Document::Part { number, count },
+        total_files,
+        sections.len(),
+    );
+
+    template.render_header(&document, out)?;
+    for section in sections {
+        write!(out, "{section}")?;
     }
+    template.render_footer(&document, out)?;
+
+    out.flush()?;
+    Ok(())
+}
 
-    writeln!(out, "  </fileContents>")?;
-    writeln!(out, "</concatenation>")?;
+pub fn write_part_index(
+    config: &RunConfig,
+    matched_files: &[PathBuf],
+    metadata: &[FileMetadata],
+    tree: Option<&str>,
+    parts: &[Part],
+    out: &mut dyn Write,
+) -> Result<()> {
+    let template = layout(config);
+    let mut document = document_context(config, &Document::Index(parts), matched_files.len(), 0);
+    set_overview_context(&mut document, config, matched_files, metadata, tree, None)?;
+
+    template.render_header(&document, out)?;
+    template.render_footer(&document, out)?;
 
     out.flush()?;
     Ok(())
 }
 
-fn write_matched_dir_list_xml(out: &mut dyn Write, matched_files: &[PathBuf]) -> Result<()> {
-    writeln!(out, "  <matchedFilesDirStructureList>")?;
+// The overview sections start out empty; only the whole bundle and the part
+// index fill them in.
+fn document_context(
+    config: &RunConfig,
+    document: &Document,
+    total: usize,
+    file_count: usize,
+) -> template::Context {
+    let mut ctx = template::Context::new();
+    ctx.set("total", total);
+    ctx.set("file_count", file_count);
+    ctx.set("xml_schema_v2", config.xml_schema == XmlSchema::V2);
+    ctx.set("schema_version", xml_schema_version(config.xml_schema));
+    config.text_headers.set_section_context(&mut ctx);
+
+    let (part, part_count, first, last) = match document {
+        Document::Whole => (0, 0, true, true),
+        Document::Part { number, count } => (*number, *count, *number == 1, number == count),
+        Document::Index(parts) => (0, parts.len(), false, false),
+    };
+    ctx.set("part", part);
+    ctx.set("part_count", part_count);
+    ctx.set("is_index", matches!(document, Document::Index(_)));
+    ctx.set(
+        "parts",
+        match document {
+            Document::Index(parts) => part_index_context(config, parts),
+            _ => Vec::new(),
+        },
+    );
+
+    // A split bundle is pasted part by part: the prompt opens the first part
+    // and closes the last.
+    let prompt = config.prompt.as_ref();
+    ctx.set(
+        "prompt",
+        prompt.map(|prompt| prompt.text()).unwrap_or_default(),
+    );
+    ctx.set(
+        "prompt_before",
+        first && prompt.is_some_and(|prompt| prompt.before()),
+    );
+    ctx.set(
+        "prompt_after",
+        last && prompt.is_some_and(|prompt| prompt.after()),
+    );
+
+    ctx.set("tree", "");
+    ctx.set("show_dir_list", false);
+    ctx.set("dir_count", 0);
+    ctx.set("dir_file_total", 0);
+    ctx.set("dirs", Vec::new());
+    ctx.set("show_metadata", false);
+    ctx.set("total_tokens", 0);
+    ctx.set("metadata", Vec::new());
+    ctx.set("files", Vec::new());
+    ctx.set("manifest", false);
+    ctx.set("budget", false);
+    ctx
+}
+
+fn set_overview_context(
+    ctx: &mut template::Context,
+    config: &RunConfig,
+    matched_files: &[PathBuf],
+    files: &[FileMetadata],
+    tree: Option<&str>,
+    budget: Option<&BudgetReport>,
+) -> Result<()> {
+    let total = matched_files.len();
+    let paths = &config.path_display;
+
+    let manifest = config
+        .manifest
+        .then(|| crate::manifest::collect(config))
+        .transpose()?;
+    crate::manifest::set_context(ctx, manifest.as_ref())?;
+
+    ctx.set("tree", tree.unwrap_or_default());
+
+    let mut dirs = Vec::new();
+    let mut dir_file_total = 0;
+    for (relative_dir, names) in group_matched_dirs(paths, matched_files) {
+        let mut dir_ctx = template::Context::new();
+        dir_ctx.set("path", relative_dir);
+        dir_ctx.set("count", names.len());
+        dir_file_total += names.len();
+        dir_ctx.set(
+            "files",
+            names
+                .into_iter()
+                .map(|name| {
+                    let mut file_ctx = template::Context::new();
+                    file_ctx.set("name", name);
+                    file_ctx
+                })
+                .collect::<Vec<_>>(),
+        );
+        dirs.push(dir_ctx);
+    }
+    ctx.set("show_dir_list", config.show_dir_list);
+    ctx.set("dir_count", dirs.len());
+    ctx.set("dir_file_total", dir_file_total);
+    ctx.set("dirs", dirs);
+
+    let metadata = metadata_section(config, files);
+    ctx.set("show_metadata", metadata.is_some());
+    ctx.set(
+        "total_tokens",
+        metadata.as_deref().map(total_tokens).unwrap_or_default(),
+    );
+    ctx.set(
+        "metadata",
+        metadata_list_context(paths, &metadata.unwrap_or_default(), total),
+    );
+    ctx.set("files", metadata_list_context(paths, files, total));
+
+    crate::budget::set_context(ctx, paths, config.budget.as_ref().zip(budget));
+    Ok(())
+}
+
+fn part_index_context(config: &RunConfig, parts: &[Part]) -> Vec<template::Context> {
+    parts
+        .iter()
+        .enumerate()
+        .map(|(index, part)| {
+            let entries: Vec<template::Context> = part
+                .entries
+                .iter()
+                .map(|entry| {
+                    let mut entry_ctx = template::Context::new();
+                    entry_ctx.set("path", config.path_display.display(&entry.path));
+                    set_chunk_context(&mut entry_ctx, entry.chunk.as_ref());
+                    entry_ctx
+                })
+                .collect();
+
+            let mut part_ctx = template::Context::new();
+            part_ctx.set("number", index + 1);
+            part_ctx.set("name", part.name.as_str());
+            part_ctx.set("count", entries.len());
+            part_ctx.set("entries", entries);
+            part_ctx
+        })
+        .collect()
+}
+
+fn file_context(
+    config: &RunConfig,
+    file_path: &Path,
+    entry: &FileMetadata,
+    index: usize,
+    total: usize,
+    chunk: Option<&FileChunk>,
+) -> template::Context {
+    let display_path = config.path_display.display(file_path);
+
+    let mut ctx = metadata_template_context(&config.path_display, entry, index, total);
+    ctx.set("path", display_path.as_str());
+    config.text_headers.set_file_context(&mut ctx, file_path);
+    set_chunk_context(&mut ctx, chunk);
+
+    let (content, encoding, line_numbers) = match chunk {
+        Some(chunk) => (present_chunk(config, chunk), "", config.line_numbers),
+        None => match read_file_body(config, file_path) {
+            FileBody::Text(text) => {
+                let (text, numbered) = present_text(config, file_path, text);
+                (text, "", numbered)
+            }
+            FileBody::Binary(bytes) => (
+                base64::engine::general_purpose::STANDARD.encode(bytes),
+                "base64",
+                false,
+            ),
+            FileBody::Unreadable(error) => {
+                if config.format == OutputFormat::Text {
+                    eprintln!("Error: Cannot read file '{display_path}'.");
+                }
+                ctx.set("error", error);
+                (String::new(), "", false)
+            }
+        },
+    };
 
-    let cwd = std::env::current_dir()?
-        .canonicalize()
-        .unwrap_or(std::env::current_dir()?);
+    ctx.set("content", content);
+    ctx.set("encoding", encoding);
+    ctx.set("line_numbers", line_numbers);
+    ctx
+}
 
+fn set_chunk_context(ctx: &mut template::Context, chunk: Option<&FileChunk>) {
+    ctx.set("chunk", chunk.is_some());
+    ctx.set("chunk_number", chunk.map_or(0, |chunk| chunk.number));
+    ctx.set("chunk_count", chunk.map_or(0, |chunk| chunk.count));
+    ctx.set(
+        "part_label",
+        chunk
+            .map(|chunk| format!(" (part {}/{})", chunk.number, chunk.count))
+            .unwrap_or_default(),
+    );
+}
+
+fn group_matched_dirs(
+    paths: &PathDisplay,
+    matched_files: &[PathBuf],
+) -> Vec<(String, Vec<String>)> {
     let mut grouped: BTreeMap<PathBuf, Vec<String>> = BTreeMap::new();
     for file in matched_files {
         let full = canonical_or_fallback(file);
@@ -175,234 +515,318 @@ fn write_matched_dir_list_xml(out: &mut dyn Write, matched_files: &[PathBuf]) ->
     let mut dirs: Vec<PathBuf> = grouped.keys().cloned().collect();
     dirs.sort_by(|a, b| sort::version_path_cmp(a, b));
 
-    for dir in dirs {
-        let relative_dir = if dir == cwd {
-            ".".to_string()
-        } else if let Ok(stripped) = dir.strip_prefix(&cwd) {
-            stripped.to_string_lossy().to_string()
+    dirs.into_iter()
+        .map(|dir| {
+            let files = grouped.remove(&dir).unwrap_or_default();
+            (paths.display(&dir), files)
+        })
+        .collect()
+}
+
+pub fn metadata_list_context(
+    paths: &PathDisplay,
+    metadata: &[FileMetadata],
+    total: usize,
+) -> Vec<template::Context> {
+    metadata
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| metadata_template_context(paths, entry, index + 1, total))
+        .collect()
+}
+
+fn metadata_attributes(entry: &FileMetadata) -> impl Iterator<Item = &(FileAttr, String)> {
+    entry
+        .attributes
+        .iter()
+        .filter(|(attr, _)| *attr != FileAttr::Tokens)
+}
+
+fn metadata_template_context(
+    paths: &PathDisplay,
+    entry: &FileMetadata,
+    index: usize,
+    total: usize,
+) -> template::Context {
+    let mut ctx = template::Context::new();
+    ctx.set("index", index);
+    ctx.set("total", total);
+    ctx.set("path", paths.display(&entry.path));
+    ctx.set("relative_path", paths.relative_to_cwd(&entry.path));
+    ctx.set("absolute_path", entry.path.to_string_lossy().to_string());
+    ctx.set(
+        "language",
+        content_language(&entry.path).unwrap_or_default(),
+    );
+    ctx.set("lines", entry.lines);
+    ctx.set("is_notebook", entry.cells.is_some());
+    ctx.set("cells", entry.cells.unwrap_or_default());
+    ctx.set("characters", entry.characters);
+    ctx.set("emitted", entry.emitted.is_some());
+    ctx.set("truncated", entry.truncated);
+    ctx.set(
+        "emitted_lines",
+        entry.emitted.map_or(entry.lines, |(lines, _)| lines),
+    );
+    ctx.set(
+        "emitted_characters",
+        entry
+            .emitted
+            .map_or(entry.characters, |(_, characters)| characters),
+    );
+    ctx.set("saved_bytes", entry.saved_bytes);
+    ctx.set("tokens", entry.tokens);
+    ctx.set("is_binary", entry.is_binary);
+    ctx.set("error", entry.read_error.clone().unwrap_or_default());
+
+    let attribute_context = |(attr, value): &(FileAttr, String)| {
+        let mut attr_ctx = template::Context::new();
+        attr_ctx.set("name", attr.name());
+        attr_ctx.set("value", value.as_str());
+        attr_ctx
+    };
+    ctx.set(
+        "attributes",
+        entry
+            .attributes
+            .iter()
+            .map(attribute_context)
+            .collect::<Vec<_>>(),
+    );
+    ctx.set(
+        "metadata_attributes",
+        metadata_attributes(entry)
+            .map(attribute_context)
+            .collect::<Vec<_>>(),
+    );
+    ctx
+}
+
+pub fn read_file_body(config: &RunConfig, path: &Path) -> FileBody {
+    match std::fs::read(path) {
+        Ok(bytes) => {
+            if config.include_binary && !text_detect::bytes_are_probably_text(&bytes) {
+                FileBody::Binary(bytes)
+            } else {
+                let text = String::from_utf8_lossy(&bytes).into_owned();
+                FileBody::Text(prepare_text(config, path, &text).unwrap_or(text))
+            }
+        }
+        Err(err) => FileBody::Unreadable(err.to_string()),
+    }
+}
+
+fn present_text(config: &RunConfig, path: &Path, text: String) -> (String, bool) {
+    if let Some(outline) = outline_text(config, path, &text) {
+        return (number_excerpt(&outline), true);
+    }
+
+    if let Some(truncated) = truncate_text(config, path, &text) {
+        return if config.line_numbers {
+            (number_excerpt(&truncated), true)
         } else {
-            dir.to_string_lossy().to_string()
+            (truncated.text(), false)
         };
+    }
+
+    if !config.line_numbers {
+        return (text, false);
+    }
 
-        let files = grouped.get(&dir).cloned().unwrap_or_default();
-        let files_joined = files
-            .into_iter()
-            .map(|name| format!("\"{name}\""))
-            .collect::<Vec<_>>()
-            .join(", ");
+    let last_line = text.lines().count().max(1);
+    (number_lines(&text, 1, digit_count(last_line)), true)
+}
 
-        writeln!(
-            out,
-            "    <dirEntry>\"{}\": [{}]</dirEntry>",
-            xml_escape_text(&relative_dir),
-            xml_escape_text(&files_joined)
-        )?;
+fn present_chunk(config: &RunConfig, chunk: &FileChunk) -> String {
+    if !config.line_numbers {
+        return chunk.text.clone();
     }
 
-    writeln!(out, "  </matchedFilesDirStructureList>")?;
-    Ok(())
+    number_lines(&chunk.text, chunk.first_line, chunk.width)
 }
 
-fn write_file_metadata_xml(out: &mut dyn Write, metadata: &[FileMetadata]) -> Result<()> {
-    writeln!(out, "  <fileMetadata count=\"{}\">", metadata.len())?;
+fn number_lines(text: &str, first_line: usize, width: usize) -> String {
+    let mut numbered = String::with_capacity(text.len() + (width + 3) * text.lines().count());
 
-    if metadata.is_empty() {
-        writeln!(out, "    <message>No files matched the criteria.</message>")?;
-        writeln!(out, "  </fileMetadata>")?;
-        return Ok(());
+    for (offset, line) in text.split_inclusive('\n').enumerate() {
+        push_numbered_line(
+            &mut numbered,
+            &(first_line + offset).to_string(),
+            width,
+            line,
+        );
     }
 
-    for entry in metadata {
-        let path = entry.path.to_string_lossy().to_string();
-        let binary_attr = if entry.is_binary { "true" } else { "false" };
+    numbered
+}
 
-        writeln!(out, "    <file binary=\"{binary_attr}\">")?;
-        writeln!(out, "      <path>{}</path>", xml_escape_text(&path))?;
+fn number_excerpt(excerpt: &Excerpt) -> String {
+    let width = digit_count(excerpt.original_lines.max(1));
+    let mut numbered = String::new();
+
+    for line in &excerpt.lines {
+        let number = line.number.map(|number| number.to_string());
+        push_numbered_line(
+            &mut numbered,
+            number.as_deref().unwrap_or(""),
+            width,
+            &line.text,
+        );
+    }
 
-        if let Some(error) = &entry.read_error {
-            writeln!(out, "      <error>{}</error>", xml_escape_text(error))?;
-        } else {
-            writeln!(out, "      <lines>{}</lines>", entry.lines)?;
-            writeln!(out, "      <characters>{}</characters>", entry.characters)?;
-        }
+    numbered
+}
 
-        writeln!(out, "    </file>")?;
+fn push_numbered_line(numbered: &mut String, number: &str, width: usize, line: &str) {
+    if line == "\n" || line == "\r\n" {
+        numbered.push_str(&format!("{number:>width$} |{line}"));
+    } else {
+        numbered.push_str(&format!("{number:>width$} | {line}"));
     }
+}
 
-    writeln!(out, "  </fileMetadata>")?;
-    Ok(())
+// Outlined files are only redacted, so their line numbers stay those of the file.
+pub fn prepare_text(config: &RunConfig, path: &Path, text: &str) -> Option<String> {
+    prepare(config, path, text).text
 }
 
-fn write_text_output(
-    config: &RunConfig,
-    matched_files: &[PathBuf],
-    tree: Option<&str>,
-    metadata: Option<&[FileMetadata]>,
-    out: &mut dyn Write,
-) -> Result<()> {
-    if let Some(tree) = tree {
-        writeln!(
-            out,
-            "--------------------------------------------------------------------------------"
-        )?;
-        writeln!(out, "# Directory Tree (from current directory)")?;
-        writeln!(
-            out,
-            "********************************************************************************"
-        )?;
-        writeln!(out, ".")?;
-        writeln!(out, "{tree}")?;
-        writeln!(
-            out,
-            "================================================================================"
-        )?;
-        writeln!(out)?;
+pub fn prepare(config: &RunConfig, path: &Path, text: &str) -> PreparedText {
+    let notebook = render_notebook(config, path, text);
+    let cells = notebook.as_ref().map(|notebook| notebook.cells);
+    let rendered = notebook.map(|notebook| notebook.text);
+
+    let found = (config.redact || config.fail_on_secret)
+        .then(|| {
+            config
+                .redactor
+                .redact(path, rendered.as_deref().unwrap_or(text))
+        })
+        .flatten();
+    let (redacted, secrets) = match found {
+        Some((redacted, secrets)) => (config.redact.then_some(redacted).or(rendered), secrets),
+        None => (rendered, BTreeMap::new()),
+    };
+
+    if is_outlined(config, path) {
+        return PreparedText {
+            text: redacted,
+            reshaped: cells.is_some(),
+            cells,
+            secrets,
+            saved_bytes: 0,
+        };
     }
 
-    if let Some(metadata) = metadata {
-        write_file_metadata_text(out, metadata)?;
+    let text = redacted.as_deref().unwrap_or(text);
+    let stripped = strip_comments(config, path, text);
+    let body = stripped.as_deref().unwrap_or(text);
+    let minified = minify_whitespace(config, path, body);
+    let saved_bytes = minified
+        .as_ref()
+        .map_or(0, |minified| body.len().saturating_sub(minified.len()));
+
+    PreparedText {
+        reshaped: cells.is_some() || stripped.is_some() || minified.is_some(),
+        text: minified.or(stripped).or(redacted),
+        cells,
+        secrets,
+        saved_bytes,
     }
+}
 
-    writeln!(
-        out,
-        "--------------------------------------------------------------------------------"
-    )?;
-    writeln!(out, "# File Contents ({} files)", matched_files.len())?;
-    writeln!(
-        out,
-        "********************************************************************************"
-    )?;
-
-    if matched_files.is_empty() {
-        writeln!(out, "No files matched the criteria.")?;
-        writeln!(
-            out,
-            "================================================================================"
-        )?;
-        out.flush()?;
-        return Ok(());
+pub fn render_notebook(config: &RunConfig, path: &Path, text: &str) -> Option<Notebook> {
+    if !notebook::is_notebook(path) {
+        return None;
     }
 
-    for (index, file_path) in matched_files.iter().enumerate() {
-        let current_file = index + 1;
-        let absolute_path = canonical_or_fallback(file_path);
-        let absolute_path_string = absolute_path.to_string_lossy();
+    notebook::render(text, config.notebook_outputs)
+}
 
-        writeln!(out)?;
-        writeln!(
-            out,
-            "--------------------------------------------------------------------------------"
-        )?;
-        writeln!(
-            out,
-            "# File {current_file}/{}: {absolute_path_string}",
-            matched_files.len()
-        )?;
-        writeln!(
-            out,
-            "********************************************************************************"
-        )?;
-
-        let bytes = std::fs::read(file_path);
-        match bytes {
-            Ok(bytes) => {
-                if config.include_binary && !text_detect::bytes_are_probably_text(&bytes) {
-                    writeln!(out, "[BINARY FILE: base64]")?;
-                    let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
-                    writeln!(out, "{encoded}")?;
-                } else {
-                    let text = String::from_utf8_lossy(&bytes);
-                    write!(out, "{text}")?;
-
-                    if !bytes.ends_with(b"\n") {
-                        writeln!(out)?;
-                    }
-                }
+pub fn content_language(path: &Path) -> Option<&'static str> {
+    if notebook::is_notebook(path) {
+        Some("markdown")
+    } else {
+        language::language_for_path(path)
+    }
+}
 
-                writeln!(out)?;
-                writeln!(out, "# EOF: {absolute_path_string}")?;
-                writeln!(
-                    out,
-                    "================================================================================"
-                )?;
-            }
-            Err(_) => {
-                eprintln!("Error: Cannot read file '{}'.", absolute_path_string);
-                writeln!(
-                    out,
-                    "================================================================================"
-                )?;
-            }
-        }
+pub fn redact_text(config: &RunConfig, path: &Path, text: &str) -> Option<String> {
+    if !config.redact {
+        return None;
     }
 
-    out.flush()?;
-    Ok(())
+    config
+        .redactor
+        .redact(path, text)
+        .map(|(redacted, _)| redacted)
 }
 
-fn write_file_metadata_text(out: &mut dyn Write, metadata: &[FileMetadata]) -> Result<()> {
-    writeln!(
-        out,
-        "--------------------------------------------------------------------------------"
-    )?;
-    writeln!(out, "# File Metadata ({} files)", metadata.len())?;
-    writeln!(
-        out,
-        "********************************************************************************"
-    )?;
-
-    if metadata.is_empty() {
-        writeln!(out, "No files matched the criteria.")?;
-        writeln!(
-            out,
-            "================================================================================"
-        )?;
-        writeln!(out)?;
-        return Ok(());
+pub fn minify_whitespace(config: &RunConfig, path: &Path, text: &str) -> Option<String> {
+    let level = config.minify_whitespace?;
+    minify::minify(text, content_language(path), level)
+}
+
+pub fn strip_comments(config: &RunConfig, path: &Path, text: &str) -> Option<String> {
+    if !config.strip_comments {
+        return None;
     }
 
-    for (index, entry) in metadata.iter().enumerate() {
-        let path = entry.path.to_string_lossy();
-        let binary_marker = if entry.is_binary { " [binary]" } else { "" };
-
-        if let Some(error) = &entry.read_error {
-            writeln!(
-                out,
-                "{}: {}{binary_marker} (error: {})",
-                index + 1,
-                path,
-                error
-            )?;
-            continue;
-        }
+    comments::strip(
+        text,
+        content_language(path),
+        config.keep_doc_comments,
+        config.line_numbers,
+    )
+}
 
-        writeln!(
-            out,
-            "{}: {}{binary_marker} (lines: {}, chars: {})",
-            index + 1,
-            path,
-            entry.lines,
-            entry.characters
-        )?;
-    }
+pub fn is_outlined(config: &RunConfig, path: &Path) -> bool {
+    config
+        .outline
+        .as_ref()
+        .is_some_and(|outline| outline.covers(&config.path_display, path))
+}
 
-    writeln!(
-        out,
-        "================================================================================"
-    )?;
-    writeln!(out)?;
-    Ok(())
+pub fn outline_text(config: &RunConfig, path: &Path, text: &str) -> Option<Excerpt> {
+    config
+        .outline
+        .as_ref()
+        .and_then(|outline| outline.apply(&config.path_display, path, text))
+}
+
+pub fn excerpt_text(config: &RunConfig, path: &Path, text: &str) -> Option<Excerpt> {
+    outline_text(config, path, text).or_else(|| truncate_text(config, path, text))
+}
+
+pub fn truncate_text(config: &RunConfig, path: &Path, text: &str) -> Option<Excerpt> {
+    config
+        .truncation
+        .as_ref()
+        .and_then(|truncation| truncation.apply(&config.path_display, path, text))
 }
 
-fn canonical_or_fallback(path: &Path) -> PathBuf {
+pub fn digit_count(value: usize) -> usize {
+    value.to_string().len()
+}
+
+pub fn canonical_or_fallback(path: &Path) -> PathBuf {
     std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
 }
 
-fn collect_file_metadata(matched_files: &[PathBuf], sort_by: MetadataSort) -> Vec<FileMetadata> {
-    let mut metadata: Vec<FileMetadata> = matched_files
+pub fn collect_file_metadata(config: &RunConfig, matched_files: &[PathBuf]) -> Vec<FileMetadata> {
+    matched_files
         .iter()
-        .map(|path| build_file_metadata(path))
-        .collect();
+        .map(|path| build_file_metadata(config, path, &config.file_attrs))
+        .collect()
+}
+
+fn metadata_section(config: &RunConfig, metadata: &[FileMetadata]) -> Option<Vec<FileMetadata>> {
+    config
+        .show_metadata
+        .then(|| sort_file_metadata(metadata, config.metadata_sort))
+}
+
+pub fn sort_file_metadata(metadata: &[FileMetadata], sort_by: MetadataSort) -> Vec<FileMetadata> {
+    let mut metadata = metadata.to_vec();
 
     match sort_by {
         MetadataSort::Lines => {
@@ -415,18 +839,27 @@ fn collect_file_metadata(matched_files: &[PathBuf], sort_by: MetadataSort) -> Ve
                     .then_with(|| a.path.cmp(&b.path))
             });
         }
+        MetadataSort::Tokens => {
+            metadata.sort_by(|a, b| b.tokens.cmp(&a.tokens).then_with(|| a.path.cmp(&b.path)));
+        }
         MetadataSort::Natural => {}
     }
 
     metadata
 }
 
-fn build_file_metadata(path: &Path) -> FileMetadata {
+pub fn total_tokens(metadata: &[FileMetadata]) -> usize {
+    metadata.iter().map(|entry| entry.tokens).sum()
+}
+
+fn build_file_metadata(config: &RunConfig, path: &Path, attrs: &[FileAttr]) -> FileMetadata {
+    let tokenizer = config.tokenizer;
     let absolute_path = canonical_or_fallback(path);
     let bytes = std::fs::read(path);
 
     match bytes {
         Ok(bytes) => {
+            let mut attributes = file_attrs::collect(attrs, path, &bytes);
             let is_text = text_detect::bytes_are_probably_text(&bytes);
 
             if is_text {
@@ -434,12 +867,42 @@ fn build_file_metadata(path: &Path) -> FileMetadata {
                 let lines = text.lines().count();
                 let characters = text.chars().count();
 
+                // Tokens measure what the bundle holds, so they follow the
+                // stripped, minified, outlined and truncated text.
+                let prepared = prepare(config, path, &text);
+                let body = prepared.text.as_deref().unwrap_or(&text);
+                let outline = outline_text(config, path, body);
+                let truncation = match outline {
+                    Some(_) => None,
+                    None => truncate_text(config, path, body),
+                };
+                let truncated = truncation.is_some();
+                let excerpt = outline.or(truncation).map(|excerpt| excerpt.text());
+                let is_excerpt = excerpt.is_some();
+                let written = excerpt.or(prepared.text).map_or(text.clone(), Cow::Owned);
+                let tokens = tokenizer.count(&written);
+
+                let emitted = (written.lines().count(), written.chars().count());
+                let changed = prepared.reshaped && emitted != (lines, characters);
+                let emitted = (is_excerpt || changed).then_some(emitted);
+
+                if attrs.contains(&FileAttr::Tokens) {
+                    attributes.push((FileAttr::Tokens, tokens.to_string()));
+                }
+
                 FileMetadata {
                     path: absolute_path,
                     lines,
+                    cells: prepared.cells,
                     characters,
+                    emitted,
+                    truncated,
+                    saved_bytes: prepared.saved_bytes,
+                    secrets: prepared.secrets,
+                    tokens,
                     is_binary: false,
                     read_error: None,
+                    attributes,
                 }
             } else {
                 let lines = count_lines_in_bytes(&bytes);
@@ -447,18 +910,33 @@ fn build_file_metadata(path: &Path) -> FileMetadata {
                 FileMetadata {
                     path: absolute_path,
                     lines,
+                    cells: None,
                     characters: bytes.len(),
+                    emitted: None,
+                    truncated: false,
+                    saved_bytes: 0,
+                    secrets: BTreeMap::new(),
+                    tokens: tokenizer
+                        .count(&base64::engine::general_purpose::STANDARD.encode(&bytes)),
                     is_binary: true,
                     read_error: None,
+                    attributes,
                 }
             }
         }
         Err(err) => FileMetadata {
             path: absolute_path,
             lines: 0,
+            cells: None,
             characters: 0,
+            emitted: None,
+            truncated: false,
+            saved_bytes: 0,
+            secrets: BTreeMap::new(),
+            tokens: 0,
             is_binary: false,
             read_error: Some(err.to_string()),
+            attributes: Vec::new(),
         },
     }
 }
@@ -477,14 +955,27 @@ fn count_lines_in_bytes(bytes: &[u8]) -> usize {
     }
 }
 
-fn xml_escape_text(input: &str) -> String {
-    input
+pub fn xml_schema_version(schema: XmlSchema) -> usize {
+    match schema {
+        XmlSchema::V1 => 1,
+        XmlSchema::V2 => 2,
+    }
+}
+
+pub fn xml_escape_text(input: &str) -> String {
+    sanitize_xml_text(input)
         .replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
 }
 
-fn sanitize_xml_text(input: &str) -> String {
+pub fn xml_escape_attr(input: &str) -> String {
+    xml_escape_text(input)
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+pub fn sanitize_xml_text(input: &str) -> String {
     input
         .chars()
         .map(|ch| {
@@ -500,15 +991,3 @@ fn sanitize_xml_text(input: &str) -> String {
         })
         .collect()
 }
-
-fn write_cdata_body(out: &mut dyn Write, input: &str) -> Result<()> {
-    for (index, part) in input.split("]]>").enumerate() {
-        if index > 0 {
-            write!(out, "]]]]><![CDATA[>")?;
-        }
-
-        write!(out, "{part}")?;
-    }
-
-    Ok(())
-}
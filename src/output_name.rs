@@ -2,6 +2,7 @@ use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 
+use crate::compress::Compression;
 use crate::config::{OutputFormat, RunConfig};
 
 pub fn resolve_output_path(config: &RunConfig, expanded_inputs: &[PathBuf]) -> Result<PathBuf> {
@@ -12,9 +13,19 @@ pub fn resolve_output_path(config: &RunConfig, expanded_inputs: &[PathBuf]) -> R
         None => (default_output_name(config, expanded_inputs)?, false),
     };
 
-    let required_ext = match config.format {
-        OutputFormat::Xml => "xml",
-        OutputFormat::Text => "txt",
+    // Strip an existing `.gz`/`.zst` so the format extension is checked on
+    // `bundle.xml` rather than `bundle.xml.gz`; it is re-added below.
+    if config.compression.is_some() && Compression::from_path(&path).is_some() {
+        path.set_extension("");
+    }
+
+    let required_ext = match (&config.template, config.format) {
+        (Some(template), _) => template.extension(),
+        (None, OutputFormat::Xml) => "xml",
+        (None, OutputFormat::Text) => "txt",
+        (None, OutputFormat::Html) => "html",
+        (None, OutputFormat::Tar) => "tar",
+        (None, OutputFormat::Zip) => "zip",
     };
 
     let has_required_ext = path
@@ -26,6 +37,11 @@ pub fn resolve_output_path(config: &RunConfig, expanded_inputs: &[PathBuf]) -> R
         path.set_extension(required_ext);
     }
 
+    if let Some(compression) = config.compression {
+        path.as_mut_os_string()
+            .push(format!(".{}", compression.extension()));
+    }
+
     let absolute = if path.is_absolute() {
         path
     } else {
@@ -0,0 +1,150 @@
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::cli::PathMode;
+
+pub const ROOT_PLACEHOLDER: &str = "$ROOT";
+pub const HOME_PLACEHOLDER: &str = "$HOME";
+
+#[derive(Debug)]
+pub struct PathDisplay {
+    mode: PathMode,
+    cwd: PathBuf,
+    root: PathBuf,
+    home: Option<PathBuf>,
+}
+
+impl PathDisplay {
+    pub fn new(mode: PathMode) -> Result<Self> {
+        let cwd = std::env::current_dir().context("Failed to get current working directory")?;
+        let cwd = cwd.canonicalize().unwrap_or(cwd);
+        let root = find_project_root(&cwd);
+        let home = std::env::var_os("HOME")
+            .map(PathBuf::from)
+            .filter(|home| home.is_absolute())
+            .map(|home| home.canonicalize().unwrap_or(home));
+
+        Ok(Self {
+            mode,
+            cwd,
+            root,
+            home,
+        })
+    }
+
+    pub fn mode(&self) -> PathMode {
+        self.mode
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn display(&self, path: &Path) -> String {
+        let absolute = self.absolute(path);
+
+        match self.mode {
+            PathMode::Absolute => absolute.to_string_lossy().to_string(),
+            PathMode::Relative => relative_string(&absolute, &self.cwd),
+            PathMode::Root => relative_string(&absolute, &self.root),
+            PathMode::Anon => self.anonymize(&absolute),
+        }
+    }
+
+    pub fn relative_to_cwd(&self, path: &Path) -> String {
+        relative_string(&self.absolute(path), &self.cwd)
+    }
+
+    pub fn absolute(&self, path: &Path) -> PathBuf {
+        let joined = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.cwd.join(path)
+        };
+
+        joined.canonicalize().unwrap_or_else(|_| normalize(&joined))
+    }
+
+    fn anonymize(&self, absolute: &Path) -> String {
+        if let Ok(stripped) = absolute.strip_prefix(&self.root) {
+            return join_placeholder(ROOT_PLACEHOLDER, stripped);
+        }
+
+        if let Some(home) = &self.home
+            && let Ok(stripped) = absolute.strip_prefix(home)
+        {
+            return join_placeholder(HOME_PLACEHOLDER, stripped);
+        }
+
+        absolute.to_string_lossy().to_string()
+    }
+}
+
+pub fn find_project_root(start: &Path) -> PathBuf {
+    start
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+        .unwrap_or(start)
+        .to_path_buf()
+}
+
+fn join_placeholder(placeholder: &str, rest: &Path) -> String {
+    if rest.as_os_str().is_empty() {
+        placeholder.to_string()
+    } else {
+        format!("{placeholder}/{}", rest.to_string_lossy())
+    }
+}
+
+fn relative_string(path: &Path, base: &Path) -> String {
+    let relative = relative_path(path, base);
+
+    if relative.as_os_str().is_empty() {
+        ".".to_string()
+    } else {
+        relative.to_string_lossy().to_string()
+    }
+}
+
+fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let path_components: Vec<Component> = path.components().collect();
+    let base_components: Vec<Component> = base.components().collect();
+
+    let shared = path_components
+        .iter()
+        .zip(&base_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    if shared == 0 {
+        return path.to_path_buf();
+    }
+
+    let mut relative = PathBuf::new();
+    for _ in shared..base_components.len() {
+        relative.push("..");
+    }
+
+    for component in &path_components[shared..] {
+        relative.push(component.as_os_str());
+    }
+
+    relative
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component.as_os_str()),
+        }
+    }
+
+    normalized
+}
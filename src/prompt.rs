@@ -0,0 +1,55 @@
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+
+use crate::cli::PromptPosition;
+
+#[derive(Debug)]
+pub struct Prompt {
+    text: String,
+    position: PromptPosition,
+}
+
+impl Prompt {
+    pub fn new(
+        text: Option<&str>,
+        file: Option<&Path>,
+        position: PromptPosition,
+    ) -> Result<Option<Self>> {
+        let text = match (text, file) {
+            (Some(text), _) => text.to_string(),
+            (None, Some(file)) => std::fs::read_to_string(file)
+                .with_context(|| format!("Cannot read prompt file \"{}\".", file.display()))?,
+            (None, None) => return Ok(None),
+        };
+
+        let text = text.trim_end().to_string();
+        if text.trim().is_empty() {
+            bail!("The prompt is empty.");
+        }
+
+        Ok(Some(Self { text, position }))
+    }
+
+    pub fn describe(&self) -> String {
+        let position = match self.position {
+            PromptPosition::Before => "before",
+            PromptPosition::After => "after",
+            PromptPosition::Both => "before and after",
+        };
+
+        format!("{} characters, {position}", self.text.chars().count())
+    }
+
+    pub fn before(&self) -> bool {
+        matches!(self.position, PromptPosition::Before | PromptPosition::Both)
+    }
+
+    pub fn after(&self) -> bool {
+        matches!(self.position, PromptPosition::After | PromptPosition::Both)
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
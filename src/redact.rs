@@ -0,0 +1,278 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use regex::{Captures, Regex};
+
+use crate::config::RunConfig;
+use crate::output::FileMetadata;
+
+const ENV_VALUE: &str = "env_value";
+
+const ENTROPY_MIN_LENGTH: usize = 16;
+const ENTROPY_MIN_BITS: f64 = 3.5;
+
+// When a pattern has a capture group, only the group is redacted.
+const BUILTIN_RULES: &[(&str, &str)] = &[
+    (
+        "private_key",
+        r"(?s)-----BEGIN [A-Z0-9 ]*PRIVATE KEY-----.*?-----END [A-Z0-9 ]*PRIVATE KEY-----",
+    ),
+    ("aws_access_key", r"\b(?:AKIA|ASIA|ABIA|ACCA)[0-9A-Z]{16}\b"),
+    (
+        "aws_secret_key",
+        r#"(?i)aws_?secret_?access_?key["']?\s*[:=]\s*["']?([A-Za-z0-9/+=]{40})\b"#,
+    ),
+    ("gcp_api_key", r"\bAIza[0-9A-Za-z_\-]{35}\b"),
+    (
+        "github_token",
+        r"\b(?:gh[pousr]_[A-Za-z0-9]{36,}|github_pat_[A-Za-z0-9_]{22,})\b",
+    ),
+    ("slack_token", r"\bxox[abposr]-[A-Za-z0-9-]{10,}\b"),
+    (
+        "slack_webhook",
+        r"https://hooks\.slack\.com/services/[A-Za-z0-9_/]+",
+    ),
+    (
+        "jwt",
+        r"\beyJ[A-Za-z0-9_-]{8,}\.eyJ[A-Za-z0-9_-]{8,}\.[A-Za-z0-9_-]{8,}",
+    ),
+];
+
+const HIGH_ENTROPY: (&str, &str) = (
+    "high_entropy",
+    r#"(?i)[A-Za-z0-9_.-]*(?:secret|token|passw(?:or)?d|pwd|api_?key|auth|credential|private_?key)[A-Za-z0-9_.-]*["']?\s*[:=]\s*["']?([A-Za-z0-9+/=_\-.~!@#$%^&*]+)"#,
+);
+
+#[derive(Debug)]
+struct Rule {
+    kind: String,
+    regex: Regex,
+    entropy: bool,
+}
+
+#[derive(Debug)]
+pub struct Redactor {
+    rules: Vec<Rule>,
+    custom: usize,
+}
+
+impl Redactor {
+    pub fn new(rule_files: &[PathBuf]) -> Result<Self> {
+        let mut rules: Vec<Rule> = BUILTIN_RULES
+            .iter()
+            .map(|(kind, pattern)| Rule {
+                kind: kind.to_string(),
+                regex: Regex::new(pattern).expect("built-in redaction rule"),
+                entropy: false,
+            })
+            .collect();
+        rules.push(Rule {
+            kind: HIGH_ENTROPY.0.to_string(),
+            regex: Regex::new(HIGH_ENTROPY.1).expect("built-in redaction rule"),
+            entropy: true,
+        });
+
+        let builtin = rules.len();
+        for path in rule_files {
+            rules.extend(read_rule_file(path)?);
+        }
+
+        Ok(Self {
+            custom: rules.len() - builtin,
+            rules,
+        })
+    }
+
+    pub fn describe(&self) -> String {
+        if self.custom == 0 {
+            "built-in rules".to_string()
+        } else {
+            format!("built-in rules + {} custom", self.custom)
+        }
+    }
+
+    // Keeps the line count so line numbers still match the file.
+    pub fn redact(&self, path: &Path, text: &str) -> Option<(String, BTreeMap<String, usize>)> {
+        let mut kinds = BTreeMap::new();
+        let mut redacted = if is_env_file(path) {
+            redact_env_values(text, &mut kinds)
+        } else {
+            text.to_string()
+        };
+
+        for rule in &self.rules {
+            let mut found = 0;
+            let replaced = rule.regex.replace_all(&redacted, |caps: &Captures| {
+                let whole = caps.get(0).expect("match");
+                let secret = caps.get(1).unwrap_or(whole);
+
+                if rule.entropy && !looks_random(secret.as_str()) {
+                    return whole.as_str().to_string();
+                }
+
+                found += 1;
+                let before = &whole.as_str()[..secret.start() - whole.start()];
+                let after = &whole.as_str()[secret.end() - whole.start()..];
+                format!(
+                    "{before}[REDACTED:{}]{}{after}",
+                    rule.kind,
+                    "\n".repeat(secret.as_str().matches('\n').count())
+                )
+            });
+
+            if found > 0 {
+                redacted = replaced.into_owned();
+                *kinds.entry(rule.kind.clone()).or_insert(0) += found;
+            }
+        }
+
+        (!kinds.is_empty()).then_some((redacted, kinds))
+    }
+}
+
+fn read_rule_file(path: &Path) -> Result<Vec<Rule>> {
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("Cannot read redaction rules \"{}\".", path.display()))?;
+
+    let mut rules = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((kind, pattern)) = line.split_once('=') else {
+            bail!(
+                "{}:{}: expected \"kind = regex\", found \"{line}\".",
+                path.display(),
+                index + 1
+            );
+        };
+        let kind = kind.trim();
+        if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            bail!(
+                "{}:{}: rule kinds use letters, digits and underscores, not \"{kind}\".",
+                path.display(),
+                index + 1
+            );
+        }
+
+        let regex = Regex::new(pattern.trim()).with_context(|| {
+            format!(
+                "{}:{}: invalid regex for \"{kind}\".",
+                path.display(),
+                index + 1
+            )
+        })?;
+        rules.push(Rule {
+            kind: kind.to_string(),
+            regex,
+            entropy: false,
+        });
+    }
+
+    Ok(rules)
+}
+
+fn is_env_file(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    let is_env = name == ".env" || name.starts_with(".env.") || name.ends_with(".env");
+    let is_documentation = [".example", ".sample", ".template", ".dist"]
+        .iter()
+        .any(|suffix| name.ends_with(suffix));
+
+    is_env && !is_documentation
+}
+
+fn redact_env_values(text: &str, kinds: &mut BTreeMap<String, usize>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut found = 0;
+
+    for line in text.split_inclusive('\n') {
+        let body = line.trim_end_matches(['\n', '\r']);
+        let ending = &line[body.len()..];
+        let trimmed = body.trim_start();
+
+        let assignment = (!trimmed.starts_with('#'))
+            .then(|| body.split_once('='))
+            .flatten()
+            .filter(|(key, value)| {
+                let key = key.trim().trim_start_matches("export ").trim();
+                !key.is_empty()
+                    && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                    && !value.trim().trim_matches(['"', '\'']).is_empty()
+            });
+
+        match assignment {
+            Some((key, _)) => {
+                found += 1;
+                out.push_str(&format!("{key}=[REDACTED:{ENV_VALUE}]{ending}"));
+            }
+            None => out.push_str(line),
+        }
+    }
+
+    if found > 0 {
+        kinds.insert(ENV_VALUE.to_string(), found);
+    }
+    out
+}
+
+fn looks_random(value: &str) -> bool {
+    value.len() >= ENTROPY_MIN_LENGTH
+        && value.chars().any(|c| c.is_ascii_digit())
+        && value.chars().any(|c| c.is_ascii_alphabetic())
+        && shannon_entropy(value) >= ENTROPY_MIN_BITS
+}
+
+fn shannon_entropy(value: &str) -> f64 {
+    let mut counts: BTreeMap<char, usize> = BTreeMap::new();
+    for c in value.chars() {
+        *counts.entry(c).or_insert(0) += 1;
+    }
+
+    let length = value.chars().count() as f64;
+    counts
+        .values()
+        .map(|&count| {
+            let p = count as f64 / length;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+pub fn print_summary(config: &RunConfig, findings: &[&FileMetadata]) {
+    let total: usize = findings
+        .iter()
+        .flat_map(|finding| finding.secrets.values())
+        .sum();
+    let verb = if config.fail_on_secret {
+        "Found"
+    } else {
+        "Redacted"
+    };
+
+    eprintln!(
+        "{verb} {total} secret{} in {} file{}:",
+        if total == 1 { "" } else { "s" },
+        findings.len(),
+        if findings.len() == 1 { "" } else { "s" }
+    );
+    for finding in findings {
+        let kinds: Vec<String> = finding
+            .secrets
+            .iter()
+            .map(|(kind, count)| format!("{kind} ({count})"))
+            .collect();
+        eprintln!(
+            "  {}: {}",
+            config.path_display.display(&finding.path),
+            kinds.join(", ")
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+
+use crate::cli;
+use crate::config::RunConfig;
+use crate::manifest;
+use crate::paths::{ROOT_PLACEHOLDER, find_project_root};
+
+pub fn run(args: cli::RerunArgs) -> Result<()> {
+    let source = crate::compress::read_to_string(&args.bundle)
+        .with_context(|| format!("Cannot read bundle \"{}\".", args.bundle.display()))?;
+    let recorded = manifest::parse_recorded_run(&source)
+        .with_context(|| format!("Cannot rerun \"{}\".", args.bundle.display()))?;
+
+    let directory = match args.directory {
+        Some(directory) => directory,
+        None => recorded_directory(&recorded.cwd)?,
+    };
+
+    if args.print {
+        let command: Vec<String> = std::iter::once("concat".to_string())
+            .chain(recorded.arguments.iter().map(|arg| shell_quote(arg)))
+            .collect();
+        println!(
+            "cd {} && {}",
+            shell_quote(&directory.to_string_lossy()),
+            command.join(" ")
+        );
+        return Ok(());
+    }
+
+    if !directory.is_dir() {
+        bail!(
+            "The recorded directory \"{}\" does not exist here; pass -C to choose another.",
+            directory.display()
+        );
+    }
+
+    if let Some(version) = &recorded.version
+        && version != env!("CARGO_PKG_VERSION")
+    {
+        eprintln!(
+            "Warning: the bundle was written by concat {version}; this is concat {}.",
+            env!("CARGO_PKG_VERSION")
+        );
+    }
+
+    std::env::set_current_dir(&directory)
+        .with_context(|| format!("Cannot change into \"{}\".", directory.display()))?;
+
+    let raw_args: Vec<OsString> = std::iter::once(OsString::from("concat"))
+        .chain(recorded.arguments.iter().map(OsString::from))
+        .collect();
+    let cli = cli::Cli::try_parse_from(&raw_args)
+        .context("The recorded arguments are not valid for this version of concat")?;
+
+    if cli.command.is_some() {
+        bail!("The recorded arguments run a subcommand, not a bundle.");
+    }
+
+    let config = RunConfig::from_cli(cli, &raw_args)?;
+    config.print_summary();
+
+    crate::run::run(config)
+}
+
+// `--paths anon` records the directory as `$ROOT/...`; it is taken to be the
+// project root around the current directory.
+fn recorded_directory(cwd: &str) -> Result<PathBuf> {
+    let Some(rest) = cwd
+        .strip_prefix(ROOT_PLACEHOLDER)
+        .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+    else {
+        return Ok(PathBuf::from(cwd));
+    };
+
+    let here = std::env::current_dir().context("Failed to get current working directory")?;
+    Ok(find_project_root(&here).join(rest.trim_start_matches('/')))
+}
+
+fn shell_quote(value: &str) -> String {
+    let safe = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./=:,@%+".contains(c));
+
+    if safe {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
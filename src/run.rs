@@ -1,24 +1,25 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
 
+use crate::cli::MetadataSort;
 use crate::clipboard;
 use crate::config::RunConfig;
+use crate::output::FileMetadata;
 
 pub fn run(config: RunConfig) -> Result<()> {
     let expanded = crate::inputs::expand_inputs(&config);
-    let output_path = crate::output_name::resolve_output_path(&config, &expanded.items)?;
+    let output_path = if config.write_to_stdout {
+        None
+    } else {
+        Some(crate::output_name::resolve_output_path(
+            &config,
+            &expanded.items,
+        )?)
+    };
 
     if config.purge_pycache {
         crate::cleanup::purge_python_cache_in_cwd(config.verbose)?;
     }
 
-    if !config.copy_to_clipboard {
-        if config.clean_concat_files {
-            crate::cleanup::delete_concat_outputs_in_cwd(config.verbose)?;
-        }
-
-        crate::cleanup::remove_existing_output_file(&output_path, config.verbose)?;
-    }
-
     let candidates = crate::discover::collect_candidate_files(&config, &expanded.items)?;
 
     let ctx = crate::filter::FilterContext {
@@ -32,10 +33,12 @@ pub fn run(config: RunConfig) -> Result<()> {
         if config.copy_to_clipboard {
             None
         } else {
-            Some(&output_path)
+            output_path.as_deref()
         },
     )?;
 
+    let metadata = crate::output::collect_file_metadata(&config, &matched);
+
     let tree = if config.show_tree {
         Some(crate::tree::build_tree(
             std::path::Path::new("."),
@@ -45,8 +48,81 @@ pub fn run(config: RunConfig) -> Result<()> {
         None
     };
 
-    if config.copy_to_clipboard {
-        let rendered = crate::output::render_output(&config, &matched, tree.as_deref())?;
+    let (matched, metadata, budget) = match &config.budget {
+        Some(budget) => {
+            let (included, metadata, report) = crate::budget::select(
+                &config,
+                budget,
+                &expanded.items,
+                matched,
+                metadata,
+                tree.as_deref(),
+            )?;
+            (included, metadata, Some(report))
+        }
+        None => (matched, metadata, None),
+    };
+
+    let findings: Vec<&FileMetadata> = metadata
+        .iter()
+        .filter(|entry| !entry.secrets.is_empty())
+        .collect();
+    if !findings.is_empty() {
+        crate::redact::print_summary(&config, &findings);
+
+        if config.fail_on_secret {
+            bail!(
+                "Secrets found in {} file{}; not writing the bundle (--fail-on-secret).",
+                findings.len(),
+                if findings.len() == 1 { "" } else { "s" }
+            );
+        }
+    }
+
+    // Cleanup runs only once the run can no longer fail on a secret, so a
+    // failing CI check keeps the previous bundle.
+    if let Some(output_path) = &output_path
+        && !config.copy_to_clipboard
+    {
+        if config.clean_concat_files {
+            crate::cleanup::delete_concat_outputs_in_cwd(config.verbose)?;
+        }
+
+        crate::cleanup::remove_existing_output_file(output_path, config.verbose)?;
+
+        if config.split.is_some() {
+            crate::split::remove_stale_parts(output_path, config.verbose)?;
+        }
+    }
+
+    if let (Some(limit), Some(output_path)) = (config.split, &output_path) {
+        if config.copy_to_clipboard {
+            crate::split::copy_split_to_clipboard(
+                &config,
+                limit,
+                output_path,
+                &matched,
+                &metadata,
+                tree.as_deref(),
+            )?;
+        } else {
+            crate::split::write_split_output(
+                &config,
+                limit,
+                output_path,
+                &matched,
+                &metadata,
+                tree.as_deref(),
+            )?;
+        }
+    } else if config.copy_to_clipboard {
+        let rendered = crate::output::render_output(
+            &config,
+            &matched,
+            &metadata,
+            tree.as_deref(),
+            budget.as_ref(),
+        )?;
         clipboard::copy_to_clipboard(&rendered)?;
 
         if config.verbose {
@@ -55,8 +131,15 @@ pub fn run(config: RunConfig) -> Result<()> {
                 rendered.len()
             );
         }
-    } else {
-        crate::output::write_output(&config, &output_path, &matched, tree.as_deref())?;
+    } else if let Some(output_path) = &output_path {
+        crate::output::write_output(
+            &config,
+            output_path,
+            &matched,
+            &metadata,
+            tree.as_deref(),
+            budget.as_ref(),
+        )?;
 
         if config.verbose {
             eprintln!(
@@ -64,7 +147,56 @@ pub fn run(config: RunConfig) -> Result<()> {
                 output_path.display()
             );
         }
+    } else {
+        crate::output::write_output_to_stdout(
+            &config,
+            &matched,
+            &metadata,
+            tree.as_deref(),
+            budget.as_ref(),
+        )?;
+
+        if config.verbose {
+            eprintln!("Concatenation written to stdout.");
+        }
+    }
+
+    print_token_summary(&config, &metadata);
+
+    if let (Some(budget), Some(report)) = (&config.budget, &budget) {
+        crate::budget::print_report(&config, budget, report, matched.len());
     }
 
     Ok(())
 }
+
+fn print_token_summary(config: &RunConfig, metadata: &[FileMetadata]) {
+    const LARGEST: usize = 3;
+
+    let metadata = crate::output::sort_file_metadata(metadata, MetadataSort::Tokens);
+
+    let largest: Vec<String> = metadata
+        .iter()
+        .filter(|entry| entry.tokens > 0)
+        .take(LARGEST)
+        .map(|entry| {
+            format!(
+                "{} ({})",
+                config.path_display.display(&entry.path),
+                entry.tokens
+            )
+        })
+        .collect();
+
+    let mut summary = format!(
+        "Tokens ({}): {} in {} files",
+        config.tokenizer.name(),
+        crate::output::total_tokens(&metadata),
+        metadata.len()
+    );
+    if !largest.is_empty() {
+        summary.push_str(&format!("; largest: {}", largest.join(", ")));
+    }
+
+    eprintln!("{summary}");
+}
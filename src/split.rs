@@ -0,0 +1,425 @@
+use std::io::{BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+use crate::cli::Tokenizer;
+use crate::clipboard;
+use crate::config::RunConfig;
+use crate::output::{self, FileBody, FileMetadata};
+
+#[derive(Clone, Copy, Debug)]
+pub enum SplitLimit {
+    Bytes(usize),
+    Tokens(usize, Tokenizer),
+}
+
+impl SplitLimit {
+    pub fn describe(self) -> String {
+        match self {
+            Self::Bytes(bytes) => format!("{bytes} bytes"),
+            Self::Tokens(tokens, tokenizer) => format!("{tokens} tokens ({})", tokenizer.name()),
+        }
+    }
+
+    fn max(self) -> usize {
+        match self {
+            Self::Bytes(max) | Self::Tokens(max, _) => max,
+        }
+    }
+
+    fn measure(self, text: &str) -> usize {
+        match self {
+            Self::Bytes(_) => text.len(),
+            Self::Tokens(_, tokenizer) => tokenizer.count(text),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FileChunk {
+    pub number: usize,
+    pub count: usize,
+    pub first_line: usize,
+    pub width: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct PartEntry {
+    pub path: PathBuf,
+    pub index: usize,
+    pub metadata: FileMetadata,
+    pub chunk: Option<FileChunk>,
+}
+
+pub struct Part {
+    pub name: String,
+    pub entries: Vec<PartEntry>,
+}
+
+#[derive(Default)]
+struct PlannedPart {
+    entries: Vec<PartEntry>,
+    sections: Vec<String>,
+}
+
+pub fn write_split_output(
+    config: &RunConfig,
+    limit: SplitLimit,
+    output_path: &Path,
+    matched_files: &[PathBuf],
+    metadata: &[FileMetadata],
+    tree: Option<&str>,
+) -> Result<()> {
+    for (path, content) in
+        render_documents(config, limit, output_path, matched_files, metadata, tree)?
+    {
+        let file = std::fs::File::create(&path)
+            .with_context(|| format!("Cannot create \"{}\".", path.display()))?;
+        let mut out = BufWriter::new(file);
+        out.write_all(content.as_bytes())?;
+        out.flush()?;
+
+        if config.verbose {
+            eprintln!("Wrote \"{}\".", path.display());
+        }
+    }
+
+    Ok(())
+}
+
+pub fn copy_split_to_clipboard(
+    config: &RunConfig,
+    limit: SplitLimit,
+    output_path: &Path,
+    matched_files: &[PathBuf],
+    metadata: &[FileMetadata],
+    tree: Option<&str>,
+) -> Result<()> {
+    let documents = render_documents(config, limit, output_path, matched_files, metadata, tree)?;
+    let total = documents.len();
+
+    for (index, (path, content)) in documents.iter().enumerate() {
+        let label = file_name(path);
+
+        if index > 0 && !wait_for_next_part(&label)? {
+            eprintln!("Stopped after {index} of {total} documents.");
+            return Ok(());
+        }
+
+        clipboard::copy_to_clipboard(content)?;
+        eprintln!(
+            "Copied {label} ({}/{total}, {} bytes) to the clipboard.",
+            index + 1,
+            content.len()
+        );
+    }
+
+    Ok(())
+}
+
+pub fn remove_stale_parts(output_path: &Path, verbose: bool) -> Result<()> {
+    let dir = match output_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(());
+    };
+
+    for entry in entries.flatten() {
+        if is_part_name(output_path, &entry.file_name().to_string_lossy()) && entry.path().is_file()
+        {
+            if verbose {
+                eprintln!("Removing stale part file: \"{}\"", entry.path().display());
+            }
+            std::fs::remove_file(entry.path())?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn is_part_file(output_path: &Path, path: &Path) -> bool {
+    let parent = |path: &Path| match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.canonicalize().ok(),
+        _ => std::env::current_dir().ok(),
+    };
+
+    path.file_name()
+        .is_some_and(|name| is_part_name(output_path, &name.to_string_lossy()))
+        && parent(path) == parent(output_path)
+}
+
+fn is_part_name(output_path: &Path, name: &str) -> bool {
+    let (Some(stem), Some(ext)) = (
+        output_path.file_stem().and_then(|stem| stem.to_str()),
+        output_path.extension().and_then(|ext| ext.to_str()),
+    ) else {
+        return false;
+    };
+
+    let Some(label) = name
+        .strip_prefix(stem)
+        .and_then(|rest| rest.strip_prefix('.'))
+        .and_then(|rest| rest.strip_suffix(ext))
+        .and_then(|rest| rest.strip_suffix('.'))
+    else {
+        return false;
+    };
+
+    label == "index"
+        || label
+            .strip_prefix("part")
+            .is_some_and(|number| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()))
+}
+
+fn render_documents(
+    config: &RunConfig,
+    limit: SplitLimit,
+    output_path: &Path,
+    matched_files: &[PathBuf],
+    metadata: &[FileMetadata],
+    tree: Option<&str>,
+) -> Result<Vec<(PathBuf, String)>> {
+    let planned = plan_parts(config, limit, matched_files, metadata)?;
+    let count = planned.len();
+
+    let mut documents = Vec::with_capacity(count + 1);
+    let mut parts = Vec::with_capacity(count);
+
+    for (index, part) in planned.into_iter().enumerate() {
+        let path = part_path(output_path, &format!("part{}", index + 1));
+        let mut buffer = Vec::new();
+        output::write_part(
+            config,
+            index + 1,
+            count,
+            matched_files.len(),
+            &part.sections,
+            &mut buffer,
+        )?;
+
+        let rendered = String::from_utf8_lossy(&buffer).into_owned();
+        if limit.measure(&rendered) > limit.max() {
+            eprintln!(
+                "Warning: \"{}\" exceeds the split limit of {}.",
+                file_name(&path),
+                limit.describe()
+            );
+        }
+
+        parts.push(Part {
+            name: file_name(&path),
+            entries: part.entries,
+        });
+        documents.push((path, rendered));
+    }
+
+    let mut buffer = Vec::new();
+    output::write_part_index(config, matched_files, metadata, tree, &parts, &mut buffer)?;
+    documents.insert(
+        0,
+        (
+            part_path(output_path, "index"),
+            String::from_utf8_lossy(&buffer).into_owned(),
+        ),
+    );
+
+    Ok(documents)
+}
+
+fn before_prompt_size(config: &RunConfig, limit: SplitLimit, total: usize) -> Result<usize> {
+    let count = total.max(3);
+    let mut first = Vec::new();
+    output::write_part(config, 1, count, total, &[], &mut first)?;
+    let mut middle = Vec::new();
+    output::write_part(config, 2, count, total, &[], &mut middle)?;
+
+    Ok(limit
+        .measure(&String::from_utf8_lossy(&first))
+        .saturating_sub(limit.measure(&String::from_utf8_lossy(&middle))))
+}
+
+fn plan_parts(
+    config: &RunConfig,
+    limit: SplitLimit,
+    matched_files: &[PathBuf],
+    metadata: &[FileMetadata],
+) -> Result<Vec<PlannedPart>> {
+    let total = matched_files.len();
+
+    let mut empty = Vec::new();
+    output::write_part(config, total, total, total, &[], &mut empty)?;
+    let overhead =
+        limit.measure(&String::from_utf8_lossy(&empty)) + before_prompt_size(config, limit, total)?;
+    let budget = limit.max().saturating_sub(overhead);
+
+    if budget == 0 {
+        bail!(
+            "The split limit of {} is too small to hold an empty part.",
+            limit.describe()
+        );
+    }
+
+    let mut parts = Vec::new();
+    let mut current = PlannedPart::default();
+    let mut used = 0;
+
+    for (index, (path, file)) in matched_files.iter().zip(metadata).enumerate() {
+        let entry = PartEntry {
+            path: path.clone(),
+            index: index + 1,
+            metadata: file.clone(),
+            chunk: None,
+        };
+        let section = output::render_part_entry(config, &entry, total)?;
+        let cost = limit.measure(&section);
+
+        let pieces = if cost <= budget {
+            vec![(entry, section, cost)]
+        } else {
+            chunk_entry(config, limit, budget, entry, section, cost, total)?
+        };
+
+        for (entry, section, cost) in pieces {
+            if used + cost > budget && !current.entries.is_empty() {
+                parts.push(std::mem::take(&mut current));
+                used = 0;
+            }
+
+            if cost > budget {
+                eprintln!(
+                    "Warning: \"{}\" does not fit in one part of {}.",
+                    config.path_display.display(&entry.path),
+                    limit.describe()
+                );
+            }
+
+            current.entries.push(entry);
+            current.sections.push(section);
+            used += cost;
+        }
+    }
+
+    if !current.entries.is_empty() {
+        parts.push(current);
+    }
+
+    Ok(parts)
+}
+
+fn chunk_entry(
+    config: &RunConfig,
+    limit: SplitLimit,
+    budget: usize,
+    entry: PartEntry,
+    section: String,
+    cost: usize,
+    total: usize,
+) -> Result<Vec<(PartEntry, String, usize)>> {
+    let FileBody::Text(text) = output::read_file_body(config, &entry.path) else {
+        return Ok(vec![(entry, section, cost)]);
+    };
+
+    // Outlined and truncated files are written whole so their line numbers
+    // and elision marker stay intact.
+    if output::excerpt_text(config, &entry.path, &text).is_some() {
+        return Ok(vec![(entry, section, cost)]);
+    }
+
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let width = output::digit_count(lines.len().max(1));
+
+    let probe = PartEntry {
+        chunk: Some(FileChunk {
+            number: lines.len(),
+            count: lines.len(),
+            first_line: 1,
+            width,
+            text: String::new(),
+        }),
+        ..entry.clone()
+    };
+    let overhead = limit.measure(&output::render_part_entry(config, &probe, total)?);
+    let available = budget.saturating_sub(overhead);
+
+    if available == 0 {
+        return Ok(vec![(entry, section, cost)]);
+    }
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    let mut start = 0;
+    let mut used = 0;
+
+    for (offset, line) in lines.iter().enumerate() {
+        let mut line_cost = limit.measure(line);
+        if config.line_numbers {
+            line_cost += limit.measure(&format!("{:>width$} | ", offset + 1));
+        }
+
+        if used + line_cost > available && offset > start {
+            ranges.push((start, offset));
+            start = offset;
+            used = 0;
+        }
+
+        used += line_cost;
+    }
+    ranges.push((start, lines.len()));
+
+    let count = ranges.len();
+    ranges
+        .into_iter()
+        .enumerate()
+        .map(|(number, (start, end))| {
+            let chunk_entry = PartEntry {
+                chunk: Some(FileChunk {
+                    number: number + 1,
+                    count,
+                    first_line: start + 1,
+                    width,
+                    text: lines[start..end].concat(),
+                }),
+                ..entry.clone()
+            };
+            let section = output::render_part_entry(config, &chunk_entry, total)?;
+            let cost = limit.measure(&section);
+            Ok((chunk_entry, section, cost))
+        })
+        .collect()
+}
+
+fn part_path(output_path: &Path, label: &str) -> PathBuf {
+    let stem = output_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    match output_path.extension() {
+        Some(ext) => {
+            output_path.with_file_name(format!("{stem}.{label}.{}", ext.to_string_lossy()))
+        }
+        None => output_path.with_file_name(format!("{stem}.{label}")),
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn wait_for_next_part(label: &str) -> Result<bool> {
+    eprint!("Press Enter to copy {label}, or q to stop: ");
+
+    let mut answer = String::new();
+    let read = match std::fs::File::open("/dev/tty") {
+        Ok(tty) => std::io::BufReader::new(tty).read_line(&mut answer)?,
+        Err(_) => std::io::stdin().lock().read_line(&mut answer)?,
+    };
+
+    Ok(read > 0 && !answer.trim().eq_ignore_ascii_case("q"))
+}
@@ -0,0 +1,635 @@
+#[derive(Debug)]
+pub struct Syntax {
+    pub line_comments: &'static [&'static str],
+    pub block_comments: &'static [(&'static str, &'static str)],
+    pub nested_block_comments: bool,
+    pub doc_comments: &'static [&'static str],
+    pub strings: &'static [&'static str],
+    pub multiline_strings: bool,
+    pub char_literals: bool,
+    pub raw_strings: bool,
+    pub word_boundaries: bool,
+    pub keywords: &'static [&'static str],
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TokenKind {
+    Code,
+    Comment,
+    DocComment,
+    Str,
+}
+
+#[derive(Debug)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+}
+
+const C_FAMILY_COMMENTS: &[(&str, &str)] = &[("/*", "*/")];
+const C_FAMILY_DOCS: &[&str] = &["/**", "/*!", "///"];
+
+const RUST: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comments: C_FAMILY_COMMENTS,
+    nested_block_comments: true,
+    doc_comments: &["///", "//!", "/**", "/*!"],
+    strings: &["\""],
+    multiline_strings: true,
+    char_literals: true,
+    raw_strings: true,
+    word_boundaries: false,
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait",
+        "true", "type", "unsafe", "use", "where", "while",
+    ],
+};
+
+const C: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comments: C_FAMILY_COMMENTS,
+    nested_block_comments: false,
+    doc_comments: C_FAMILY_DOCS,
+    strings: &["\""],
+    multiline_strings: false,
+    char_literals: true,
+    raw_strings: false,
+    word_boundaries: false,
+    keywords: &[
+        "auto",
+        "bool",
+        "break",
+        "case",
+        "char",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "delete",
+        "do",
+        "double",
+        "else",
+        "enum",
+        "extern",
+        "false",
+        "float",
+        "for",
+        "goto",
+        "if",
+        "inline",
+        "int",
+        "long",
+        "namespace",
+        "new",
+        "nullptr",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "short",
+        "signed",
+        "sizeof",
+        "static",
+        "struct",
+        "switch",
+        "template",
+        "this",
+        "true",
+        "typedef",
+        "union",
+        "unsigned",
+        "using",
+        "virtual",
+        "void",
+        "volatile",
+        "while",
+    ],
+};
+
+const JAVA: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comments: C_FAMILY_COMMENTS,
+    nested_block_comments: false,
+    doc_comments: C_FAMILY_DOCS,
+    strings: &["\"\"\"", "\""],
+    multiline_strings: false,
+    char_literals: true,
+    raw_strings: false,
+    word_boundaries: false,
+    keywords: &[
+        "abstract",
+        "boolean",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "do",
+        "double",
+        "else",
+        "enum",
+        "extends",
+        "false",
+        "final",
+        "finally",
+        "float",
+        "for",
+        "fun",
+        "if",
+        "implements",
+        "import",
+        "instanceof",
+        "int",
+        "interface",
+        "long",
+        "new",
+        "null",
+        "object",
+        "override",
+        "package",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "throws",
+        "true",
+        "try",
+        "val",
+        "var",
+        "void",
+        "when",
+        "while",
+    ],
+};
+
+const GO: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comments: C_FAMILY_COMMENTS,
+    nested_block_comments: false,
+    doc_comments: &[],
+    strings: &["\"", "`"],
+    multiline_strings: false,
+    char_literals: true,
+    raw_strings: false,
+    word_boundaries: false,
+    keywords: &[
+        "break",
+        "case",
+        "chan",
+        "const",
+        "continue",
+        "default",
+        "defer",
+        "else",
+        "fallthrough",
+        "false",
+        "for",
+        "func",
+        "go",
+        "goto",
+        "if",
+        "import",
+        "interface",
+        "map",
+        "nil",
+        "package",
+        "range",
+        "return",
+        "select",
+        "struct",
+        "switch",
+        "true",
+        "type",
+        "var",
+    ],
+};
+
+const JAVASCRIPT: Syntax = Syntax {
+    line_comments: &["//"],
+    block_comments: C_FAMILY_COMMENTS,
+    nested_block_comments: false,
+    doc_comments: C_FAMILY_DOCS,
+    strings: &["\"", "'", "`"],
+    multiline_strings: false,
+    char_literals: false,
+    raw_strings: false,
+    word_boundaries: false,
+    keywords: &[
+        "async",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "enum",
+        "export",
+        "extends",
+        "false",
+        "finally",
+        "for",
+        "from",
+        "function",
+        "if",
+        "implements",
+        "import",
+        "in",
+        "instanceof",
+        "interface",
+        "let",
+        "new",
+        "null",
+        "of",
+        "private",
+        "protected",
+        "public",
+        "return",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "true",
+        "try",
+        "type",
+        "typeof",
+        "undefined",
+        "var",
+        "void",
+        "while",
+        "yield",
+    ],
+};
+
+const CSS: Syntax = Syntax {
+    line_comments: &[],
+    block_comments: C_FAMILY_COMMENTS,
+    nested_block_comments: false,
+    doc_comments: &[],
+    strings: &["\"", "'"],
+    multiline_strings: false,
+    char_literals: false,
+    raw_strings: false,
+    word_boundaries: false,
+    keywords: &["!important", "@import", "@media", "@keyframes"],
+};
+
+const PYTHON: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comments: &[],
+    nested_block_comments: false,
+    doc_comments: &[],
+    strings: &["\"\"\"", "'''", "\"", "'"],
+    multiline_strings: false,
+    char_literals: false,
+    raw_strings: false,
+    word_boundaries: false,
+    keywords: &[
+        "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
+        "continue", "def", "del", "elif", "else", "except", "finally", "for", "from", "global",
+        "if", "import", "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return",
+        "try", "while", "with", "yield",
+    ],
+};
+
+const RUBY: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comments: &[("=begin", "=end")],
+    nested_block_comments: false,
+    doc_comments: &[],
+    strings: &["\"", "'"],
+    multiline_strings: true,
+    char_literals: false,
+    raw_strings: false,
+    word_boundaries: false,
+    keywords: &[
+        "begin", "break", "case", "class", "def", "do", "else", "elsif", "end", "ensure", "false",
+        "for", "if", "in", "module", "next", "nil", "require", "rescue", "return", "self", "then",
+        "true", "unless", "until", "when", "while", "yield",
+    ],
+};
+
+const SHELL: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comments: &[],
+    nested_block_comments: false,
+    doc_comments: &[],
+    strings: &["\"", "'"],
+    multiline_strings: true,
+    char_literals: false,
+    raw_strings: false,
+    word_boundaries: true,
+    keywords: &[
+        "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+        "in", "local", "return", "then", "until", "while",
+    ],
+};
+
+const SQL: Syntax = Syntax {
+    line_comments: &["--"],
+    block_comments: C_FAMILY_COMMENTS,
+    nested_block_comments: false,
+    doc_comments: &[],
+    strings: &["'", "\""],
+    multiline_strings: true,
+    char_literals: false,
+    raw_strings: false,
+    word_boundaries: false,
+    keywords: &[
+        "ALTER", "AND", "AS", "BY", "CREATE", "DELETE", "DROP", "FROM", "GROUP", "HAVING", "INDEX",
+        "INSERT", "INTO", "JOIN", "KEY", "LEFT", "LIMIT", "NOT", "NULL", "ON", "OR", "ORDER",
+        "PRIMARY", "SELECT", "SET", "TABLE", "UPDATE", "VALUES", "WHERE", "WITH",
+    ],
+};
+
+const MARKUP: Syntax = Syntax {
+    line_comments: &[],
+    block_comments: &[("<!--", "-->")],
+    nested_block_comments: false,
+    doc_comments: &[],
+    strings: &[],
+    multiline_strings: false,
+    char_literals: false,
+    raw_strings: false,
+    word_boundaries: false,
+    keywords: &[],
+};
+
+const YAML: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comments: &[],
+    nested_block_comments: false,
+    doc_comments: &[],
+    strings: &["\"", "'"],
+    multiline_strings: false,
+    char_literals: false,
+    raw_strings: false,
+    word_boundaries: true,
+    keywords: &["true", "false", "null", "yes", "no"],
+};
+
+const TOML: Syntax = Syntax {
+    line_comments: &["#"],
+    block_comments: &[],
+    nested_block_comments: false,
+    doc_comments: &[],
+    strings: &["\"\"\"", "'''", "\"", "'"],
+    multiline_strings: false,
+    char_literals: false,
+    raw_strings: false,
+    word_boundaries: false,
+    keywords: &["true", "false"],
+};
+
+const JSON: Syntax = Syntax {
+    line_comments: &[],
+    block_comments: &[],
+    nested_block_comments: false,
+    doc_comments: &[],
+    strings: &["\""],
+    multiline_strings: false,
+    char_literals: false,
+    raw_strings: false,
+    word_boundaries: false,
+    keywords: &["true", "false", "null"],
+};
+
+pub fn syntax_for(language: &str) -> Option<&'static Syntax> {
+    let syntax = match language {
+        "rust" => &RUST,
+        "c" | "cpp" | "csharp" | "objectivec" | "swift" | "scss" => &C,
+        "java" | "kotlin" | "scala" => &JAVA,
+        "go" => &GO,
+        "javascript" | "jsx" | "typescript" | "tsx" => &JAVASCRIPT,
+        "css" => &CSS,
+        "python" => &PYTHON,
+        "ruby" => &RUBY,
+        "shell" | "make" | "dockerfile" | "cmake" | "perl" | "r" | "elixir" | "powershell" => {
+            &SHELL
+        }
+        "sql" => &SQL,
+        "html" | "xml" | "markdown" => &MARKUP,
+        "yaml" => &YAML,
+        "toml" => &TOML,
+        "json" => &JSON,
+        _ => return None,
+    };
+
+    Some(syntax)
+}
+
+pub fn tokenize<'a>(text: &'a str, syntax: &Syntax) -> Vec<Token<'a>> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut code_start = 0;
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let rest = &text[pos..];
+        let at_boundary = pos == 0 || bytes[pos - 1].is_ascii_whitespace();
+        let after_word = pos > 0 && is_word_byte(bytes[pos - 1]);
+
+        let matched = if let Some(end) = match_raw_string(rest, syntax, after_word) {
+            Some((TokenKind::Str, end))
+        } else if let Some((kind, end)) = match_block_comment(rest, syntax) {
+            Some((kind, end))
+        } else if syntax
+            .line_comments
+            .iter()
+            .any(|marker| rest.starts_with(marker))
+            && (at_boundary || !syntax.word_boundaries)
+        {
+            let end = rest.find('\n').unwrap_or(rest.len());
+            let is_doc = is_doc_marker(rest, syntax);
+            let kind = if is_doc {
+                TokenKind::DocComment
+            } else {
+                TokenKind::Comment
+            };
+            Some((kind, end))
+        } else if let Some(end) = match_char_literal(rest, syntax) {
+            Some((TokenKind::Str, end))
+        } else {
+            match_string(rest, syntax, after_word).map(|end| (TokenKind::Str, end))
+        };
+
+        match matched {
+            Some((kind, len)) => {
+                if code_start < pos {
+                    tokens.push(Token {
+                        kind: TokenKind::Code,
+                        text: &text[code_start..pos],
+                    });
+                }
+
+                tokens.push(Token {
+                    kind,
+                    text: &text[pos..pos + len],
+                });
+                pos += len;
+                code_start = pos;
+            }
+            None => {
+                pos += rest.chars().next().map_or(1, char::len_utf8);
+            }
+        }
+    }
+
+    if code_start < bytes.len() {
+        tokens.push(Token {
+            kind: TokenKind::Code,
+            text: &text[code_start..],
+        });
+    }
+
+    tokens
+}
+
+fn match_block_comment(rest: &str, syntax: &Syntax) -> Option<(TokenKind, usize)> {
+    let (open, close) = syntax
+        .block_comments
+        .iter()
+        .find(|(open, _)| rest.starts_with(open))?;
+
+    let mut depth = 1;
+    let mut pos = open.len();
+
+    while pos < rest.len() && depth > 0 {
+        let tail = &rest[pos..];
+
+        if tail.starts_with(close) {
+            depth -= 1;
+            pos += close.len();
+        } else if syntax.nested_block_comments && tail.starts_with(open) {
+            depth += 1;
+            pos += open.len();
+        } else {
+            pos += tail.chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    let is_doc = is_doc_marker(rest, syntax) && !rest.starts_with(&format!("{open}{close}"));
+
+    let kind = if is_doc {
+        TokenKind::DocComment
+    } else {
+        TokenKind::Comment
+    };
+
+    Some((kind, pos))
+}
+
+fn match_raw_string(rest: &str, syntax: &Syntax, after_word: bool) -> Option<usize> {
+    if !syntax.raw_strings || after_word {
+        return None;
+    }
+
+    let body = rest
+        .strip_prefix("br")
+        .or_else(|| rest.strip_prefix("cr"))
+        .or_else(|| rest.strip_prefix('r'))?;
+
+    let hashes = body.bytes().take_while(|&byte| byte == b'#').count();
+    if body.as_bytes().get(hashes) != Some(&b'"') {
+        return None;
+    }
+
+    let prefix_len = rest.len() - body.len();
+    let terminator = format!("\"{}", "#".repeat(hashes));
+    let content_start = prefix_len + hashes + 1;
+
+    let end = rest[content_start..]
+        .find(&terminator)
+        .map_or(rest.len(), |offset| {
+            content_start + offset + terminator.len()
+        });
+
+    Some(end)
+}
+
+fn match_char_literal(rest: &str, syntax: &Syntax) -> Option<usize> {
+    if !syntax.char_literals || !rest.starts_with('\'') {
+        return None;
+    }
+
+    let mut chars = rest[1..].char_indices();
+    let (_, first) = chars.next()?;
+
+    if first == '\\' {
+        let close = rest[2..].find('\'')?;
+        let end = 2 + close + 1;
+        return (close <= 10 && !rest[..end].contains('\n')).then_some(end);
+    }
+
+    if first == '\'' || first == '\n' {
+        return None;
+    }
+
+    let (offset, next) = chars.next()?;
+    (next == '\'').then_some(1 + offset + 1)
+}
+
+fn match_string(rest: &str, syntax: &Syntax, after_word: bool) -> Option<usize> {
+    let quote = syntax
+        .strings
+        .iter()
+        .find(|quote| rest.starts_with(**quote))?;
+
+    if syntax.word_boundaries && quote.len() == 1 && quote == &"'" && after_word {
+        return None;
+    }
+
+    let multiline = syntax.multiline_strings || quote.len() == 3 || *quote == "`";
+    let mut pos = quote.len();
+
+    while pos < rest.len() {
+        let tail = &rest[pos..];
+
+        if tail.starts_with(quote) {
+            return Some(pos + quote.len());
+        }
+
+        if tail.starts_with('\\') && *quote != "`" {
+            pos += 1;
+            pos += rest[pos..].chars().next().map_or(0, char::len_utf8);
+            continue;
+        }
+
+        if tail.starts_with('\n') && !multiline {
+            return Some(pos);
+        }
+
+        pos += tail.chars().next().map_or(1, char::len_utf8);
+    }
+
+    Some(rest.len())
+}
+
+fn is_doc_marker(rest: &str, syntax: &Syntax) -> bool {
+    syntax.doc_comments.iter().any(|doc| {
+        let repeated = doc
+            .chars()
+            .last()
+            .is_some_and(|last| rest[doc.len()..].starts_with(last) && last != '!');
+
+        rest.starts_with(doc) && !repeated
+    })
+}
+
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
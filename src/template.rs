@@ -0,0 +1,531 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use anyhow::{Context as _, Result, bail};
+
+const BUILTIN_XML: &str = include_str!("templates/xml.tmpl");
+const BUILTIN_TEXT: &str = include_str!("templates/text.tmpl");
+const PARTIALS: &[(&str, &str)] = &[(
+    "file_metadata",
+    include_str!("templates/file_metadata.tmpl"),
+)];
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Str(String),
+    Num(usize),
+    Bool(bool),
+    List(Vec<Context>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    values: BTreeMap<String, Value>,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, key: &str, value: impl Into<Value>) {
+        self.values.insert(key.to_string(), value.into());
+    }
+
+    fn get(&self, key: &str) -> Option<&Value> {
+        self.values.get(key)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Self::Str(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::Str(value.to_string())
+    }
+}
+
+impl From<usize> for Value {
+    fn from(value: usize) -> Self {
+        Self::Num(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<Vec<Context>> for Value {
+    fn from(value: Vec<Context>) -> Self {
+        Self::List(value)
+    }
+}
+
+#[derive(Debug)]
+pub struct Template {
+    name: String,
+    extension: String,
+    header: Vec<Node>,
+    file: Vec<Node>,
+    footer: Vec<Node>,
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Var {
+        name: String,
+        filters: Vec<Filter>,
+    },
+    If {
+        name: String,
+        negate: bool,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    Each {
+        name: String,
+        body: Vec<Node>,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Filter {
+    Xml,
+    Attr,
+    Cdata,
+    Newline,
+    Trim,
+    Upper,
+    Lower,
+}
+
+#[derive(Debug)]
+enum Token {
+    Text(String),
+    Var(String),
+    Open(String),
+    Else,
+    Close(String),
+    Comment,
+}
+
+impl Template {
+    pub fn load(spec: &str) -> Result<Self> {
+        match spec {
+            "xml" => Self::parse("xml", "xml", BUILTIN_XML),
+            "text" => Self::parse("text", "txt", BUILTIN_TEXT),
+            _ => {
+                let path = Path::new(spec);
+                let source = std::fs::read_to_string(path).with_context(|| {
+                    format!("Cannot read template file \"{}\".", path.display())
+                })?;
+
+                Self::parse(spec, &extension_for_template_path(path), &source)
+            }
+        }
+    }
+
+    pub fn builtin(name: &str) -> &'static Self {
+        static XML: OnceLock<Template> = OnceLock::new();
+        static TEXT: OnceLock<Template> = OnceLock::new();
+
+        let cell = if name == "text" { &TEXT } else { &XML };
+        cell.get_or_init(|| Self::load(name).expect("built-in templates are valid"))
+    }
+
+    pub fn parse(name: &str, extension: &str, source: &str) -> Result<Self> {
+        let tokens = strip_standalone_lines(
+            tokenize(source).with_context(|| format!("Invalid template \"{name}\"."))?,
+        );
+
+        let mut iter = tokens.into_iter().peekable();
+        let mut template = Self {
+            name: name.to_string(),
+            extension: extension.to_string(),
+            header: Vec::new(),
+            file: Vec::new(),
+            footer: Vec::new(),
+        };
+
+        while let Some(token) = iter.next() {
+            match token {
+                Token::Text(text) if text.trim().is_empty() => {}
+                Token::Comment => {}
+                Token::Open(tag) => {
+                    let section = tag.trim();
+                    let nodes = parse_nodes(&mut iter, Some(section))
+                        .with_context(|| format!("Invalid template \"{name}\"."))?;
+
+                    match section {
+                        "header" => template.header = nodes,
+                        "file" => template.file = nodes,
+                        "footer" => template.footer = nodes,
+                        other => bail!(
+                            "Invalid template \"{name}\": unknown section '{other}' (expected header, file, or footer)."
+                        ),
+                    }
+                }
+                _ => bail!(
+                    "Invalid template \"{name}\": content must be inside {{{{#header}}}}, {{{{#file}}}}, or {{{{#footer}}}} sections."
+                ),
+            }
+        }
+
+        Ok(template)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn extension(&self) -> &str {
+        &self.extension
+    }
+
+    pub fn render_header(&self, ctx: &Context, out: &mut dyn Write) -> Result<()> {
+        self.render_section(&self.header, ctx, out)
+    }
+
+    pub fn render_file(&self, ctx: &Context, out: &mut dyn Write) -> Result<()> {
+        self.render_section(&self.file, ctx, out)
+    }
+
+    pub fn render_footer(&self, ctx: &Context, out: &mut dyn Write) -> Result<()> {
+        self.render_section(&self.footer, ctx, out)
+    }
+
+    fn render_section(&self, nodes: &[Node], ctx: &Context, out: &mut dyn Write) -> Result<()> {
+        render_nodes(nodes, &[ctx], out)
+            .with_context(|| format!("Failed to render template \"{}\".", self.name))
+    }
+}
+
+fn extension_for_template_path(path: &Path) -> String {
+    let stem = Path::new(path.file_stem().unwrap_or_default());
+
+    stem.extension()
+        .and_then(|ext| ext.to_str())
+        .filter(|ext| !ext.is_empty())
+        .unwrap_or("txt")
+        .to_ascii_lowercase()
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            push_text(&mut tokens, &rest[..start]);
+        }
+
+        let after_open = &rest[start + 2..];
+        let Some(end) = after_open.find("}}") else {
+            bail!("unclosed '{{{{' tag");
+        };
+
+        let tag = after_open[..end].trim();
+        rest = &after_open[end + 2..];
+
+        if let Some(name) = tag.strip_prefix('>') {
+            let name = name.trim();
+            let Some((_, source)) = PARTIALS.iter().find(|(partial, _)| *partial == name) else {
+                bail!("unknown partial '{name}'");
+            };
+
+            // The tag's own line break ends the partial's last line.
+            for token in tokenize(source.strip_suffix('\n').unwrap_or(source))? {
+                match token {
+                    Token::Text(text) => push_text(&mut tokens, &text),
+                    token => tokens.push(token),
+                }
+            }
+            continue;
+        }
+
+        tokens.push(if let Some(name) = tag.strip_prefix('#') {
+            Token::Open(name.trim().to_string())
+        } else if let Some(name) = tag.strip_prefix('/') {
+            Token::Close(name.trim().to_string())
+        } else if tag == "else" {
+            Token::Else
+        } else if tag.starts_with('!') {
+            Token::Comment
+        } else if tag.is_empty() {
+            bail!("empty '{{{{}}}}' tag");
+        } else {
+            Token::Var(tag.to_string())
+        });
+    }
+
+    if !rest.is_empty() {
+        push_text(&mut tokens, rest);
+    }
+
+    Ok(tokens)
+}
+
+fn push_text(tokens: &mut Vec<Token>, text: &str) {
+    match tokens.last_mut() {
+        Some(Token::Text(last)) => last.push_str(text),
+        _ => tokens.push(Token::Text(text.to_string())),
+    }
+}
+
+fn strip_standalone_lines(mut tokens: Vec<Token>) -> Vec<Token> {
+    let mut begins_line = vec![false; tokens.len()];
+    if let Some(first) = begins_line.first_mut() {
+        *first = true;
+    }
+
+    for index in 0..tokens.len() {
+        let is_block = matches!(
+            tokens[index],
+            Token::Open(_) | Token::Close(_) | Token::Else | Token::Comment
+        );
+
+        if !is_block {
+            continue;
+        }
+
+        let before_ok = match index.checked_sub(1).map(|prev| &tokens[prev]) {
+            None => true,
+            Some(Token::Text(text)) => {
+                let line_start = text.rfind('\n').map_or(0, |pos| pos + 1);
+                let indent_only = text[line_start..].chars().all(|ch| ch == ' ' || ch == '\t');
+
+                indent_only && (line_start > 0 || begins_line[index - 1])
+            }
+            Some(_) => false,
+        };
+
+        let after_ok = match tokens.get(index + 1) {
+            None => true,
+            Some(Token::Text(text)) => {
+                let line_end = text.find('\n').unwrap_or(text.len());
+                text[..line_end]
+                    .trim_end_matches('\r')
+                    .chars()
+                    .all(|ch| ch == ' ' || ch == '\t')
+            }
+            Some(_) => false,
+        };
+
+        if !(before_ok && after_ok) {
+            continue;
+        }
+
+        if index > 0
+            && let Token::Text(text) = &mut tokens[index - 1]
+        {
+            let line_start = text.rfind('\n').map_or(0, |pos| pos + 1);
+            text.truncate(line_start);
+        }
+
+        if let Some(Token::Text(text)) = tokens.get_mut(index + 1) {
+            match text.find('\n') {
+                Some(pos) => {
+                    text.drain(..=pos);
+                }
+                None => text.clear(),
+            }
+
+            begins_line[index + 1] = true;
+        }
+    }
+
+    tokens
+}
+
+fn parse_nodes(
+    iter: &mut std::iter::Peekable<std::vec::IntoIter<Token>>,
+    closing: Option<&str>,
+) -> Result<Vec<Node>> {
+    let (nodes, ended_with_else) = parse_block(iter, closing)?;
+
+    if ended_with_else {
+        bail!("'{{{{else}}}}' is only valid inside '{{{{#if}}}}' or '{{{{#unless}}}}'");
+    }
+
+    Ok(nodes)
+}
+
+fn parse_block(
+    iter: &mut std::iter::Peekable<std::vec::IntoIter<Token>>,
+    closing: Option<&str>,
+) -> Result<(Vec<Node>, bool)> {
+    let mut nodes = Vec::new();
+
+    while let Some(token) = iter.next() {
+        match token {
+            Token::Text(text) => {
+                if !text.is_empty() {
+                    nodes.push(Node::Text(text));
+                }
+            }
+            Token::Comment => {}
+            Token::Var(expr) => nodes.push(parse_var(&expr)?),
+            Token::Else => return Ok((nodes, true)),
+            Token::Close(name) => {
+                return match closing {
+                    Some(expected) if expected == name => Ok((nodes, false)),
+                    Some(expected) => {
+                        bail!("expected '{{{{/{expected}}}}}' but found '{{{{/{name}}}}}'")
+                    }
+                    None => bail!("unexpected '{{{{/{name}}}}}'"),
+                };
+            }
+            Token::Open(tag) => {
+                let mut parts = tag.split_whitespace();
+                let keyword = parts.next().unwrap_or_default().to_string();
+                let Some(name) = parts.next().map(str::to_string) else {
+                    bail!("'{{{{#{keyword}}}}}' requires a variable name");
+                };
+
+                match keyword.as_str() {
+                    "if" | "unless" => {
+                        let (then, ended_with_else) = parse_block(iter, Some(&keyword))?;
+                        let otherwise = if ended_with_else {
+                            parse_nodes(iter, Some(&keyword))?
+                        } else {
+                            Vec::new()
+                        };
+
+                        nodes.push(Node::If {
+                            name,
+                            negate: keyword == "unless",
+                            then,
+                            otherwise,
+                        });
+                    }
+                    "each" => {
+                        let body = parse_nodes(iter, Some("each"))?;
+                        nodes.push(Node::Each { name, body });
+                    }
+                    other => bail!("unknown block '{{{{#{other}}}}}'"),
+                }
+            }
+        }
+    }
+
+    match closing {
+        Some(expected) => bail!("missing '{{{{/{expected}}}}}'"),
+        None => Ok((nodes, false)),
+    }
+}
+
+fn parse_var(expr: &str) -> Result<Node> {
+    let mut parts = expr.split('|').map(str::trim);
+    let name = parts.next().unwrap_or_default().to_string();
+
+    let filters = parts
+        .map(|filter| {
+            Ok(match filter {
+                "xml" => Filter::Xml,
+                "attr" => Filter::Attr,
+                "cdata" => Filter::Cdata,
+                "newline" => Filter::Newline,
+                "trim" => Filter::Trim,
+                "upper" => Filter::Upper,
+                "lower" => Filter::Lower,
+                other => bail!("unknown filter '{other}' in '{{{{{expr}}}}}'"),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Node::Var { name, filters })
+}
+
+fn lookup<'a>(scopes: &[&'a Context], name: &str) -> Result<&'a Value> {
+    scopes
+        .iter()
+        .rev()
+        .find_map(|scope| scope.get(name))
+        .with_context(|| format!("unknown variable '{name}'"))
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Str(text) => !text.is_empty(),
+        Value::Num(num) => *num != 0,
+        Value::Bool(flag) => *flag,
+        Value::List(items) => !items.is_empty(),
+    }
+}
+
+fn render_nodes(nodes: &[Node], scopes: &[&Context], out: &mut dyn Write) -> Result<()> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.write_all(text.as_bytes())?,
+            Node::Var { name, filters } => {
+                let mut text = match lookup(scopes, name)? {
+                    Value::Str(text) => text.clone(),
+                    Value::Num(num) => num.to_string(),
+                    Value::Bool(flag) => flag.to_string(),
+                    Value::List(_) => bail!("variable '{name}' is a list; use '{{{{#each}}}}'"),
+                };
+
+                for filter in filters {
+                    text = apply_filter(*filter, text);
+                }
+
+                out.write_all(text.as_bytes())?;
+            }
+            Node::If {
+                name,
+                negate,
+                then,
+                otherwise,
+            } => {
+                let truthy = is_truthy(lookup(scopes, name)?);
+                let branch = if truthy != *negate { then } else { otherwise };
+                render_nodes(branch, scopes, out)?;
+            }
+            Node::Each { name, body } => {
+                let Value::List(items) = lookup(scopes, name)? else {
+                    bail!("variable '{name}' is not a list");
+                };
+
+                for (index, item) in items.iter().enumerate() {
+                    let mut position = Context::new();
+                    position.set("@first", index == 0);
+                    position.set("@last", index + 1 == items.len());
+
+                    let mut inner = scopes.to_vec();
+                    inner.push(&position);
+                    inner.push(item);
+                    render_nodes(body, &inner, out)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn apply_filter(filter: Filter, text: String) -> String {
+    match filter {
+        Filter::Xml => crate::output::xml_escape_text(&text),
+        Filter::Attr => crate::output::xml_escape_attr(&text),
+        Filter::Cdata => crate::output::sanitize_xml_text(&text).replace("]]>", "]]]]><![CDATA[>"),
+        Filter::Newline => {
+            if text.ends_with('\n') {
+                text
+            } else {
+                text + "\n"
+            }
+        }
+        Filter::Trim => text.trim().to_string(),
+        Filter::Upper => text.to_uppercase(),
+        Filter::Lower => text.to_lowercase(),
+    }
+}
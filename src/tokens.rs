@@ -0,0 +1,55 @@
+use tiktoken_rs::CoreBPE;
+
+use crate::cli::Tokenizer;
+
+impl Tokenizer {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Cl100k => "cl100k",
+            Self::O200k => "o200k",
+            Self::Approx => "approx",
+        }
+    }
+
+    pub fn count(self, text: &str) -> usize {
+        match self {
+            Self::Cl100k => count_bpe(tiktoken_rs::cl100k_base_singleton(), text),
+            Self::O200k => count_bpe(tiktoken_rs::o200k_base_singleton(), text),
+            Self::Approx => count_approximate(text),
+        }
+    }
+}
+
+fn count_bpe(bpe: &CoreBPE, text: &str) -> usize {
+    bpe.encode_ordinary(text).len()
+}
+
+// Word runs cost a token per four characters, repeated symbols one per eight.
+fn count_approximate(text: &str) -> usize {
+    let mut tokens = 0;
+    let mut word_len: usize = 0;
+    let mut symbol: Option<(char, usize)> = None;
+
+    for c in text.chars() {
+        if c.is_alphanumeric() || c == '_' {
+            tokens += symbol.take().map_or(0, |(_, len)| len.div_ceil(8));
+            word_len += 1;
+            continue;
+        }
+
+        tokens += word_len.div_ceil(4);
+        word_len = 0;
+
+        match symbol {
+            Some((previous, ref mut len)) if previous == c => *len += 1,
+            _ => {
+                tokens += symbol.take().map_or(0, |(_, len)| len.div_ceil(8));
+                if !c.is_whitespace() {
+                    symbol = Some((c, 1));
+                }
+            }
+        }
+    }
+
+    tokens + word_len.div_ceil(4) + symbol.map_or(0, |(_, len)| len.div_ceil(8))
+}
@@ -0,0 +1,298 @@
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+use globset::{Glob, GlobMatcher};
+
+use crate::cli::TruncateStrategy;
+use crate::paths::PathDisplay;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TruncateUnit {
+    Lines,
+    Bytes,
+}
+
+impl TruncateUnit {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Lines => "lines",
+            Self::Bytes => "bytes",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Truncation {
+    unit: TruncateUnit,
+    default: Option<(TruncateStrategy, usize)>,
+    rules: Vec<TruncateRule>,
+}
+
+#[derive(Debug)]
+struct TruncateRule {
+    pattern: String,
+    matcher: GlobMatcher,
+    strategy: TruncateStrategy,
+    limit: Option<usize>,
+}
+
+pub struct EmittedLine {
+    pub number: Option<usize>,
+    pub text: String,
+}
+
+pub struct Excerpt {
+    pub lines: Vec<EmittedLine>,
+    pub original_lines: usize,
+}
+
+impl Excerpt {
+    pub fn text(&self) -> String {
+        self.lines.iter().map(|line| line.text.as_str()).collect()
+    }
+}
+
+impl Truncation {
+    pub fn new(
+        lines: Option<usize>,
+        bytes: Option<usize>,
+        strategy: TruncateStrategy,
+        rules: &[String],
+    ) -> Result<Option<Self>> {
+        let (unit, limit) = match (lines, bytes) {
+            (Some(0), _) => bail!("--truncate-lines must be greater than zero."),
+            (_, Some(0)) => bail!("--truncate-bytes must be greater than zero."),
+            (Some(lines), _) => (TruncateUnit::Lines, Some(lines)),
+            (None, Some(bytes)) => (TruncateUnit::Bytes, Some(bytes)),
+            (None, None) => (TruncateUnit::Lines, None),
+        };
+
+        let rules = rules
+            .iter()
+            .map(|rule| parse_rule(rule, limit))
+            .collect::<Result<Vec<_>>>()?;
+
+        if limit.is_none() && rules.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            unit,
+            default: limit.map(|limit| (strategy, limit)),
+            rules,
+        }))
+    }
+
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some((strategy, limit)) = self.default {
+            parts.push(format!(
+                "{} {limit} {}",
+                strategy_name(strategy),
+                self.unit.as_str()
+            ));
+        }
+        for rule in &self.rules {
+            let limit = rule.limit.or(self.default.map(|(_, limit)| limit));
+            parts.push(format!(
+                "{}: {} {} {}",
+                rule.pattern,
+                strategy_name(rule.strategy),
+                limit.unwrap_or_default(),
+                self.unit.as_str()
+            ));
+        }
+        parts.join(", ")
+    }
+
+    pub fn apply(&self, paths: &PathDisplay, path: &Path, text: &str) -> Option<Excerpt> {
+        let relative = paths.relative_to_cwd(path);
+        let name = path.file_name().unwrap_or_default();
+
+        let (strategy, limit) = match self
+            .rules
+            .iter()
+            .find(|rule| rule.matcher.is_match(&relative) || rule.matcher.is_match(name))
+        {
+            Some(rule) => (
+                rule.strategy,
+                rule.limit.or(self.default.map(|(_, limit)| limit))?,
+            ),
+            None => self.default?,
+        };
+
+        match self.unit {
+            TruncateUnit::Lines => truncate_lines(text, strategy, limit),
+            TruncateUnit::Bytes => truncate_bytes(text, strategy, limit),
+        }
+    }
+}
+
+fn parse_rule(rule: &str, default_limit: Option<usize>) -> Result<TruncateRule> {
+    let Some((pattern, spec)) = rule.rsplit_once('=') else {
+        bail!("--truncate-glob takes GLOB=STRATEGY[:N], not \"{rule}\".");
+    };
+
+    let (strategy, limit) = match spec.split_once(':') {
+        Some((strategy, limit)) => {
+            let limit: usize =
+                limit
+                    .parse()
+                    .ok()
+                    .filter(|&limit| limit > 0)
+                    .with_context(|| {
+                        format!("--truncate-glob limit must be a positive number, not \"{limit}\".")
+                    })?;
+            (strategy, Some(limit))
+        }
+        None => (spec, None),
+    };
+
+    let Ok(strategy) = TruncateStrategy::from_str(strategy, true) else {
+        bail!("--truncate-glob strategy must be head, tail or head+tail, not \"{strategy}\".");
+    };
+
+    if limit.is_none() && default_limit.is_none() {
+        bail!(
+            "--truncate-glob \"{rule}\" needs a limit (GLOB=STRATEGY:N) or --truncate-lines / --truncate-bytes."
+        );
+    }
+
+    Ok(TruncateRule {
+        pattern: pattern.to_string(),
+        matcher: Glob::new(pattern)?.compile_matcher(),
+        strategy,
+        limit,
+    })
+}
+
+fn strategy_name(strategy: TruncateStrategy) -> &'static str {
+    match strategy {
+        TruncateStrategy::Head => "head",
+        TruncateStrategy::Tail => "tail",
+        TruncateStrategy::HeadTail => "head+tail",
+    }
+}
+
+fn split_limit(strategy: TruncateStrategy, limit: usize) -> (usize, usize) {
+    match strategy {
+        TruncateStrategy::Head => (limit, 0),
+        TruncateStrategy::Tail => (0, limit),
+        TruncateStrategy::HeadTail => (limit.div_ceil(2), limit / 2),
+    }
+}
+
+fn truncate_lines(text: &str, strategy: TruncateStrategy, limit: usize) -> Option<Excerpt> {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    if lines.len() <= limit {
+        return None;
+    }
+
+    let (head, tail) = split_limit(strategy, limit);
+    let tail_start = lines.len() - tail;
+    let numbered = |range: std::ops::Range<usize>| {
+        range.map(|index| EmittedLine {
+            number: Some(index + 1),
+            text: lines[index].to_string(),
+        })
+    };
+
+    let mut emitted: Vec<EmittedLine> = numbered(0..head).collect();
+    push_marker(&mut emitted, tail_start - head, TruncateUnit::Lines);
+    emitted.extend(numbered(tail_start..lines.len()));
+
+    Some(Excerpt {
+        lines: emitted,
+        original_lines: lines.len(),
+    })
+}
+
+fn truncate_bytes(text: &str, strategy: TruncateStrategy, limit: usize) -> Option<Excerpt> {
+    if text.len() <= limit {
+        return None;
+    }
+
+    let (head, tail) = split_limit(strategy, limit);
+    let head_end = snap_head(text, head);
+    let tail_start = snap_tail(text, text.len() - tail).max(head_end);
+
+    let mut emitted = split_numbered(&text[..head_end], 1);
+    push_marker(&mut emitted, tail_start - head_end, TruncateUnit::Bytes);
+    let first_tail_line = text[..tail_start].matches('\n').count() + 1;
+    emitted.extend(split_numbered(&text[tail_start..], first_tail_line));
+
+    Some(Excerpt {
+        lines: emitted,
+        original_lines: text.lines().count(),
+    })
+}
+
+fn snap_head(text: &str, max: usize) -> usize {
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    match text[..end].rfind('\n') {
+        Some(newline) => newline + 1,
+        None => end,
+    }
+}
+
+fn snap_tail(text: &str, min: usize) -> usize {
+    let mut start = min;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+
+    if start == 0 || text.as_bytes()[start - 1] == b'\n' {
+        return start;
+    }
+
+    match text[start..].find('\n') {
+        Some(newline) if start + newline + 1 < text.len() => start + newline + 1,
+        _ => start,
+    }
+}
+
+fn split_numbered(text: &str, first_line: usize) -> Vec<EmittedLine> {
+    text.split_inclusive('\n')
+        .enumerate()
+        .map(|(offset, line)| EmittedLine {
+            number: Some(first_line + offset),
+            text: line.to_string(),
+        })
+        .collect()
+}
+
+fn push_marker(emitted: &mut Vec<EmittedLine>, elided: usize, unit: TruncateUnit) {
+    if let Some(last) = emitted.last_mut()
+        && !last.text.ends_with('\n')
+    {
+        last.text.push('\n');
+    }
+
+    emitted.push(EmittedLine {
+        number: None,
+        text: format!(
+            "[... {} {} elided ...]\n",
+            group_digits(elided),
+            unit.as_str()
+        ),
+    });
+}
+
+fn group_digits(value: usize) -> String {
+    let digits = value.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    grouped
+}
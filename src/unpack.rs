@@ -0,0 +1,134 @@
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+
+use crate::bundle::{self, BundleFile};
+use crate::cli::{self, ExistingFiles};
+use crate::paths::{HOME_PLACEHOLDER, ROOT_PLACEHOLDER};
+
+enum Action {
+    Create,
+    Overwrite,
+    Skip,
+}
+
+pub fn run(args: cli::UnpackArgs) -> Result<()> {
+    let mut parsed = Vec::new();
+    for path in &args.bundles {
+        let source = crate::compress::read_to_string(path)
+            .with_context(|| format!("Cannot read bundle \"{}\".", path.display()))?;
+        parsed.extend(
+            bundle::parse_bundle(&source)
+                .with_context(|| format!("Cannot parse bundle \"{}\".", path.display()))?,
+        );
+    }
+    let files = bundle::join_parts(parsed)?;
+
+    let mut plan: Vec<(&BundleFile, PathBuf, Action)> = Vec::with_capacity(files.len());
+    for file in &files {
+        let destination = resolve_destination(&args.directory, &file.path, args.allow_escape)?;
+
+        let action = match (destination.exists(), args.existing) {
+            (false, _) => Action::Create,
+            (true, ExistingFiles::Overwrite) => Action::Overwrite,
+            (true, ExistingFiles::Skip) => Action::Skip,
+            (true, ExistingFiles::Error) => bail!(
+                "\"{}\" already exists. Pass --existing skip or --existing overwrite.",
+                destination.display()
+            ),
+        };
+
+        plan.push((file, destination, action));
+    }
+
+    let mut written = 0;
+    for (file, destination, action) in plan {
+        let verb = match (&action, args.dry_run) {
+            (Action::Skip, true) => "would skip",
+            (Action::Skip, false) => "skipped",
+            (Action::Create, true) => "would create",
+            (Action::Create, false) => "created",
+            (Action::Overwrite, true) => "would overwrite",
+            (Action::Overwrite, false) => "overwrote",
+        };
+
+        if !matches!(action, Action::Skip) {
+            written += 1;
+
+            if !args.dry_run {
+                write_file(&destination, &file.content)?;
+            }
+        }
+
+        let binary_marker = if file.is_binary { " [binary]" } else { "" };
+        println!(
+            "{verb} {}{binary_marker} ({} bytes)",
+            destination.display(),
+            file.content.len()
+        );
+    }
+
+    if args.dry_run {
+        println!(
+            "Dry run: {written} of {} files would be written.",
+            files.len()
+        );
+    } else {
+        println!("Unpacked {written} of {} files.", files.len());
+    }
+
+    Ok(())
+}
+
+fn write_file(destination: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Cannot create directory \"{}\".", parent.display()))?;
+    }
+
+    std::fs::write(destination, content)
+        .with_context(|| format!("Cannot write \"{}\".", destination.display()))
+}
+
+fn resolve_destination(target: &Path, bundle_path: &str, allow_escape: bool) -> Result<PathBuf> {
+    if let Some(rest) = strip_placeholder(bundle_path, ROOT_PLACEHOLDER) {
+        return resolve_destination(target, rest, allow_escape);
+    }
+
+    if let Some(rest) = strip_placeholder(bundle_path, HOME_PLACEHOLDER) {
+        if !allow_escape {
+            bail!(
+                "Refusing to unpack \"{bundle_path}\" outside the target directory. Pass --allow-escape to allow it."
+            );
+        }
+
+        let Some(home) = std::env::var_os("HOME") else {
+            bail!("Cannot unpack \"{bundle_path}\": $HOME is not set.");
+        };
+        return Ok(PathBuf::from(home).join(rest));
+    }
+
+    let path = Path::new(bundle_path);
+    if path.file_name().is_none() {
+        bail!("Bundle entry \"{bundle_path}\" does not name a file.");
+    }
+
+    let escapes = path.components().any(|component| {
+        matches!(
+            component,
+            Component::ParentDir | Component::RootDir | Component::Prefix(_)
+        )
+    });
+
+    if escapes && !allow_escape {
+        bail!(
+            "Refusing to unpack \"{bundle_path}\" outside the target directory. Pass --allow-escape to allow it."
+        );
+    }
+
+    Ok(target.join(path))
+}
+
+fn strip_placeholder<'a>(path: &'a str, placeholder: &str) -> Option<&'a str> {
+    path.strip_prefix(placeholder)?.strip_prefix('/')
+}
@@ -0,0 +1,692 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+use roxmltree::{Document, Node};
+
+use crate::cli;
+
+pub const SCHEMA: &str = include_str!("../schema/concatenation.xsd");
+
+const XS: &str = "http://www.w3.org/2001/XMLSchema";
+
+// The lexical form of xs:dateTime.
+const DATE_TIME: &str = r"-?[0-9]{4,}-[0-9]{2}-[0-9]{2}T[0-9]{2}:[0-9]{2}:[0-9]{2}(\.[0-9]+)?(Z|[+-][0-9]{2}:[0-9]{2})?";
+
+// Count attributes the schema types as plain integers: (element, attribute, counted element).
+const TOTALS: &[(&str, &str, &str)] = &[
+    ("tokenBudget", "omitted", "omittedFile"),
+    ("partIndex", "parts", "part"),
+    ("matchedFilesDirStructureList", "directories", "directory"),
+    ("matchedFilesDirStructureList", "files", "file"),
+];
+
+type XsNode<'s> = Node<'s, 'static>;
+
+#[derive(Clone, Copy)]
+enum Type<'s> {
+    Builtin(&'s str),
+    Simple(XsNode<'s>),
+    Complex(XsNode<'s>),
+}
+
+enum Content<'s> {
+    Empty,
+    Text(Type<'s>),
+    Elements(XsNode<'s>),
+}
+
+// An element of a content model whose choices have been resolved.
+struct Slot<'s> {
+    name: &'s str,
+    required: bool,
+    repeated: bool,
+    decl: XsNode<'s>,
+}
+
+pub struct ValidationError {
+    pub line: u32,
+    pub column: u32,
+    pub message: String,
+}
+
+pub struct BundleSummary {
+    pub schema_version: u32,
+    pub files: usize,
+}
+
+pub fn run(args: cli::ValidateArgs) -> Result<()> {
+    if args.print_schema {
+        print!("{SCHEMA}");
+        return Ok(());
+    }
+
+    let Some(path) = args.file else {
+        bail!("No bundle given to validate.");
+    };
+
+    let source = crate::compress::read_to_string(&path)
+        .with_context(|| format!("Cannot read bundle \"{}\".", path.display()))?;
+
+    match validate_bundle(&source) {
+        Ok(summary) => {
+            println!(
+                "{}: valid (schemaVersion {}, {} files)",
+                path.display(),
+                summary.schema_version,
+                summary.files
+            );
+            Ok(())
+        }
+        Err(errors) => {
+            report_errors(&path, &errors);
+            bail!(
+                "\"{}\" is not a valid concat bundle ({} error{}).",
+                path.display(),
+                errors.len(),
+                if errors.len() == 1 { "" } else { "s" }
+            );
+        }
+    }
+}
+
+fn report_errors(path: &Path, errors: &[ValidationError]) {
+    for error in errors {
+        eprintln!(
+            "{}:{}:{}: {}",
+            path.display(),
+            error.line,
+            error.column,
+            error.message
+        );
+    }
+}
+
+pub fn validate_bundle(source: &str) -> std::result::Result<BundleSummary, Vec<ValidationError>> {
+    let doc = match Document::parse(source) {
+        Ok(doc) => doc,
+        Err(err) => {
+            let pos = err.pos();
+            return Err(vec![ValidationError {
+                line: pos.row,
+                column: pos.col,
+                message: format!("malformed XML: {err}"),
+            }]);
+        }
+    };
+
+    let schema = Document::parse(SCHEMA).expect("the bundled schema is well-formed");
+
+    let mut validator = Validator {
+        doc: &doc,
+        schema: schema.root_element(),
+        patterns: HashMap::new(),
+        errors: Vec::new(),
+        schema_version: 1,
+        files: 0,
+    };
+
+    validator.check_document();
+
+    if validator.errors.is_empty() {
+        Ok(BundleSummary {
+            schema_version: validator.schema_version,
+            files: validator.files,
+        })
+    } else {
+        Err(validator.errors)
+    }
+}
+
+// Checks a bundle against the element and type declarations of `SCHEMA`,
+// which uses a small subset of XSD: sequences, choices, simple content,
+// and simple types restricted by enumerations and patterns.
+struct Validator<'a, 'input, 's> {
+    doc: &'a Document<'input>,
+    schema: XsNode<'s>,
+    patterns: HashMap<&'s str, Regex>,
+    errors: Vec<ValidationError>,
+    schema_version: u32,
+    files: usize,
+}
+
+impl<'a, 'input, 's> Validator<'a, 'input, 's> {
+    fn error(&mut self, node: Node, message: impl Into<String>) {
+        let pos = self.doc.text_pos_at(node.range().start);
+        self.errors.push(ValidationError {
+            line: pos.row,
+            column: pos.col,
+            message: message.into(),
+        });
+    }
+
+    fn check_document(&mut self) {
+        let root = self.doc.root_element();
+        let name = root.tag_name().name();
+
+        let Some(decl) = self.global("element", name) else {
+            let expected: Vec<String> = xs_children(self.schema)
+                .filter(|child| child.tag_name().name() == "element")
+                .filter_map(|child| child.attribute("name"))
+                .map(|name| format!("<{name}>"))
+                .collect();
+            self.error(
+                root,
+                format!(
+                    "root element must be {}, found <{name}>",
+                    expected.join(" or ")
+                ),
+            );
+            return;
+        };
+
+        self.check_element(root, decl);
+
+        if root.attribute("schemaVersion") == Some("2") {
+            self.schema_version = 2;
+        }
+        self.files = root
+            .children()
+            .filter(|child| child.has_tag_name("fileContents"))
+            .flat_map(|contents| contents.children())
+            .filter(|child| child.has_tag_name("file"))
+            .count();
+
+        self.check_consistency(root);
+    }
+
+    fn check_element(&mut self, node: Node<'a, 'input>, decl: XsNode<'s>) {
+        match self.declared_type(decl) {
+            Type::Complex(complex) => self.check_complex(node, complex),
+            simple => {
+                self.check_attributes(node, &[]);
+                self.check_text(node, simple);
+            }
+        }
+    }
+
+    fn check_complex(&mut self, node: Node<'a, 'input>, complex: XsNode<'s>) {
+        let mut attributes = Vec::new();
+        let mut content = Content::Empty;
+
+        for child in xs_children(complex) {
+            match child.tag_name().name() {
+                "attribute" => attributes.push(child),
+                "sequence" | "choice" => content = Content::Elements(child),
+                "simpleContent" => {
+                    for extension in xs_children(child) {
+                        content = Content::Text(
+                            self.type_named(extension.attribute("base").unwrap_or("xs:string")),
+                        );
+                        attributes.extend(
+                            xs_children(extension)
+                                .filter(|attr| attr.tag_name().name() == "attribute"),
+                        );
+                    }
+                }
+                other => self.error(
+                    node,
+                    format!(
+                        "<{}> is declared with unsupported <xs:{other}>",
+                        node.tag_name().name()
+                    ),
+                ),
+            }
+        }
+
+        self.check_attributes(node, &attributes);
+
+        match content {
+            Content::Empty => self.check_empty(node),
+            Content::Text(ty) => self.check_text(node, ty),
+            Content::Elements(particle) => {
+                let present: Vec<&str> = node
+                    .children()
+                    .filter(|child| child.is_element())
+                    .map(|child| child.tag_name().name())
+                    .collect();
+                let mut slots = Vec::new();
+                resolve_slots(particle, &present, false, &mut slots);
+
+                for (child, decl) in self.check_sequence(node, &slots) {
+                    self.check_element(child, decl);
+                }
+
+                self.check_count(node, particle);
+            }
+        }
+    }
+
+    fn check_sequence(
+        &mut self,
+        parent: Node<'a, 'input>,
+        slots: &[Slot<'s>],
+    ) -> Vec<(Node<'a, 'input>, XsNode<'s>)> {
+        let parent_name = parent.tag_name().name();
+        let mut accepted = Vec::new();
+        let mut position = 0;
+        let mut seen_at_position = 0;
+
+        for child in parent.children() {
+            if child.is_text() {
+                if !child.text().unwrap_or_default().trim().is_empty() {
+                    self.error(child, format!("unexpected text inside <{parent_name}>"));
+                }
+                continue;
+            }
+
+            if !child.is_element() {
+                continue;
+            }
+
+            let name = child.tag_name().name();
+            let Some(index) = (position..slots.len()).find(|&index| slots[index].name == name)
+            else {
+                self.error(child, format!("unexpected <{name}> inside <{parent_name}>"));
+                continue;
+            };
+
+            if index == position {
+                if seen_at_position > 0 && !slots[index].repeated {
+                    self.error(
+                        child,
+                        format!("<{name}> may appear only once inside <{parent_name}>"),
+                    );
+                    continue;
+                }
+
+                seen_at_position += 1;
+            } else {
+                for missing in missing_required(&slots[position..index], seen_at_position) {
+                    self.error(child, format!("missing <{missing}> before <{name}>"));
+                }
+
+                position = index;
+                seen_at_position = 1;
+            }
+
+            accepted.push((child, slots[index].decl));
+        }
+
+        for missing in missing_required(&slots[position..], seen_at_position) {
+            self.error(
+                parent,
+                format!("<{parent_name}> is missing required <{missing}>"),
+            );
+        }
+
+        accepted
+    }
+
+    fn check_attributes(&mut self, node: Node, decls: &[XsNode<'s>]) {
+        let element = node.tag_name().name();
+
+        for attr in node.attributes() {
+            if !decls
+                .iter()
+                .any(|decl| decl.attribute("name") == Some(attr.name()))
+            {
+                self.error(
+                    node,
+                    format!("unexpected attribute \"{}\" on <{element}>", attr.name()),
+                );
+            }
+        }
+
+        for decl in decls {
+            let name = decl.attribute("name").unwrap_or_default();
+            match node.attribute(name) {
+                None if decl.attribute("use") == Some("required") => self.error(
+                    node,
+                    format!("<{element}> is missing required attribute \"{name}\""),
+                ),
+                None => {}
+                Some(value) => {
+                    let ty = self.declared_type(*decl);
+                    if let Some(problem) = self.value_problem(ty, value) {
+                        self.error(
+                            node,
+                            format!("attribute \"{name}\" {problem}, found \"{value}\""),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn check_text(&mut self, node: Node, ty: Type<'s>) {
+        let name = node.tag_name().name();
+        if let Some(child) = node.children().find(|child| child.is_element()) {
+            self.error(
+                child,
+                format!(
+                    "<{name}> may only contain text, found <{}>",
+                    child.tag_name().name()
+                ),
+            );
+            return;
+        }
+
+        let text: String = node
+            .children()
+            .filter(|child| child.is_text())
+            .filter_map(|child| child.text())
+            .collect();
+        if let Some(problem) = self.value_problem(ty, &text) {
+            self.error(node, format!("<{name}> {problem}, found \"{text}\""));
+        }
+    }
+
+    fn check_empty(&mut self, node: Node) {
+        if node.has_children() {
+            self.error(node, format!("<{}> must be empty", node.tag_name().name()));
+        }
+    }
+
+    // A `count` attribute gives the number of elements listed inside.
+    fn check_count(&mut self, node: Node, particle: XsNode<'s>) {
+        let Some(count) = node
+            .attribute("count")
+            .and_then(|count| count.parse::<usize>().ok())
+        else {
+            return;
+        };
+        let Some(listed) = element_names(particle)
+            .into_iter()
+            .find(|&name| name != "message")
+        else {
+            return;
+        };
+
+        let actual = node
+            .children()
+            .filter(|child| child.has_tag_name(listed))
+            .count();
+        if count != actual {
+            self.error(
+                node,
+                format!(
+                    "<{}> count=\"{count}\" but it contains {actual} <{listed}> elements",
+                    node.tag_name().name()
+                ),
+            );
+        }
+    }
+
+    // What the schema cannot express: totals, part numbers within their part
+    // count, and the directory list that goes with each schemaVersion.
+    fn check_consistency(&mut self, root: Node) {
+        for node in root.descendants().filter(|node| node.is_element()) {
+            let name = node.tag_name().name();
+
+            if let Some(value) = node.attribute("part")
+                && let Some((number, count)) = value.split_once('/')
+                && let (Ok(number), Ok(count)) = (number.parse::<usize>(), count.parse::<usize>())
+                && number > count
+            {
+                self.error(
+                    node,
+                    format!("attribute \"part\" must not exceed the part count, found \"{value}\""),
+                );
+            }
+
+            for &(element, attr, counted) in TOTALS {
+                if name != element {
+                    continue;
+                }
+                let Some(expected) = node
+                    .attribute(attr)
+                    .and_then(|value| value.parse::<usize>().ok())
+                else {
+                    continue;
+                };
+
+                let actual = node
+                    .descendants()
+                    .filter(|child| child.has_tag_name(counted))
+                    .count();
+                if expected != actual {
+                    self.error(
+                        node,
+                        format!(
+                            "{attr}=\"{expected}\" but <{name}> contains {actual} <{counted}> elements"
+                        ),
+                    );
+                }
+            }
+
+            if name == "matchedFilesDirStructureList" {
+                self.check_dir_list(node);
+            }
+        }
+    }
+
+    fn check_dir_list(&mut self, node: Node) {
+        let version = self.schema_version;
+        let listed = if version == 2 {
+            "directory"
+        } else {
+            "dirEntry"
+        };
+
+        for child in node.children().filter(|child| child.is_element()) {
+            if !child.has_tag_name(listed) {
+                self.error(
+                    child,
+                    format!(
+                        "schemaVersion {version} lists directories as <{listed}>, found <{}>",
+                        child.tag_name().name()
+                    ),
+                );
+            }
+        }
+
+        if version == 2 {
+            for attr in ["directories", "files"] {
+                if node.attribute(attr).is_none() {
+                    self.error(
+                        node,
+                        format!(
+                            "<matchedFilesDirStructureList> is missing attribute \"{attr}\", required by schemaVersion 2"
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn global(&self, kind: &str, name: &str) -> Option<XsNode<'s>> {
+        xs_children(self.schema)
+            .find(|child| child.tag_name().name() == kind && child.attribute("name") == Some(name))
+    }
+
+    fn type_named(&self, name: &'s str) -> Type<'s> {
+        if let Some(builtin) = name.strip_prefix("xs:") {
+            return Type::Builtin(builtin);
+        }
+
+        self.global("complexType", name)
+            .map(Type::Complex)
+            .or_else(|| self.global("simpleType", name).map(Type::Simple))
+            .unwrap_or(Type::Builtin(name))
+    }
+
+    fn declared_type(&self, decl: XsNode<'s>) -> Type<'s> {
+        if let Some(name) = decl.attribute("type") {
+            return self.type_named(name);
+        }
+
+        xs_children(decl)
+            .find_map(|child| match child.tag_name().name() {
+                "complexType" => Some(Type::Complex(child)),
+                "simpleType" => Some(Type::Simple(child)),
+                _ => None,
+            })
+            .unwrap_or(Type::Builtin("string"))
+    }
+
+    // The builtin a type is derived from; only xs:string keeps its whitespace.
+    fn primitive(&self, ty: Type<'s>) -> &'s str {
+        match ty {
+            Type::Builtin(name) => name,
+            Type::Simple(simple) => xs_children(simple)
+                .find_map(|restriction| restriction.attribute("base"))
+                .map_or("string", |base| self.primitive(self.type_named(base))),
+            Type::Complex(_) => "string",
+        }
+    }
+
+    fn value_problem(&mut self, ty: Type<'s>, value: &str) -> Option<String> {
+        let value = if self.primitive(ty) == "string" {
+            value
+        } else {
+            value.trim()
+        };
+
+        let restriction = match ty {
+            Type::Builtin(name) => return self.builtin_problem(name, value),
+            Type::Complex(_) => return Some("must not have a complex schema type".to_string()),
+            Type::Simple(simple) => {
+                xs_children(simple).find(|child| child.tag_name().name() == "restriction")?
+            }
+        };
+
+        let base = self.type_named(restriction.attribute("base").unwrap_or("xs:string"));
+        if let Some(problem) = self.value_problem(base, value) {
+            return Some(problem);
+        }
+
+        let allowed: Vec<&str> = facets(restriction, "enumeration").collect();
+        if !allowed.is_empty() && !allowed.contains(&value) {
+            let allowed: Vec<String> = allowed.iter().map(|value| format!("\"{value}\"")).collect();
+            return Some(format!("must be one of {}", allowed.join(", ")));
+        }
+
+        for pattern in facets(restriction, "pattern") {
+            if !self.matches(pattern, value) {
+                return Some(format!("must match the pattern \"{pattern}\""));
+            }
+        }
+
+        None
+    }
+
+    fn builtin_problem(&mut self, name: &str, value: &str) -> Option<String> {
+        let (valid, expected) = match name {
+            "string" | "anySimpleType" => (true, ""),
+            "nonNegativeInteger" => (
+                value.parse::<u64>().is_ok(),
+                "must be a non-negative integer",
+            ),
+            "positiveInteger" => (
+                value.parse::<u64>().is_ok_and(|number| number > 0),
+                "must be a positive integer",
+            ),
+            "boolean" => (
+                matches!(value, "true" | "false" | "1" | "0"),
+                "must be \"true\" or \"false\"",
+            ),
+            "dateTime" => (self.matches(DATE_TIME, value), "must be a date and time"),
+            _ => return Some(format!("has unsupported schema type \"{name}\"")),
+        };
+
+        (!valid).then(|| expected.to_string())
+    }
+
+    // XSD patterns always match the whole value.
+    fn matches(&mut self, pattern: &'s str, value: &str) -> bool {
+        self.patterns
+            .entry(pattern)
+            .or_insert_with(|| {
+                Regex::new(&format!("^(?:{pattern})$")).expect("schema patterns are valid")
+            })
+            .is_match(value)
+    }
+}
+
+fn xs_children<'s>(node: XsNode<'s>) -> impl Iterator<Item = XsNode<'s>> {
+    node.children().filter(|child| {
+        child.is_element()
+            && child.tag_name().namespace() == Some(XS)
+            && child.tag_name().name() != "annotation"
+    })
+}
+
+fn facets<'s>(restriction: XsNode<'s>, facet: &'s str) -> impl Iterator<Item = &'s str> {
+    xs_children(restriction)
+        .filter(move |child| child.tag_name().name() == facet)
+        .filter_map(|child| child.attribute("value"))
+}
+
+// Flattens a content model into its element slots, taking the branch of each
+// choice that holds the elements present.
+fn resolve_slots<'s>(
+    particle: XsNode<'s>,
+    present: &[&str],
+    optional: bool,
+    slots: &mut Vec<Slot<'s>>,
+) {
+    let optional = optional || particle.attribute("minOccurs") == Some("0");
+
+    match particle.tag_name().name() {
+        "element" => slots.push(Slot {
+            name: particle.attribute("name").unwrap_or_default(),
+            required: !optional,
+            repeated: particle
+                .attribute("maxOccurs")
+                .is_some_and(|max| max != "1"),
+            decl: particle,
+        }),
+        "sequence" => {
+            for child in xs_children(particle) {
+                resolve_slots(child, present, optional, slots);
+            }
+        }
+        "choice" => {
+            let branches: Vec<XsNode> = xs_children(particle).collect();
+            let branch = branches
+                .iter()
+                .find(|branch| {
+                    element_names(**branch)
+                        .iter()
+                        .any(|name| present.contains(name))
+                })
+                .or_else(|| branches.iter().find(|branch| emptiable(**branch)))
+                .or(branches.first());
+
+            if let Some(branch) = branch {
+                resolve_slots(*branch, present, optional, slots);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn element_names<'s>(particle: XsNode<'s>) -> Vec<&'s str> {
+    match particle.tag_name().name() {
+        "element" => particle.attribute("name").into_iter().collect(),
+        _ => xs_children(particle).flat_map(element_names).collect(),
+    }
+}
+
+fn emptiable(particle: XsNode) -> bool {
+    if particle.attribute("minOccurs") == Some("0") {
+        return true;
+    }
+
+    match particle.tag_name().name() {
+        "sequence" => xs_children(particle).all(emptiable),
+        "choice" => xs_children(particle).any(emptiable),
+        _ => false,
+    }
+}
+
+fn missing_required<'s>(slots: &[Slot<'s>], seen_first: usize) -> Vec<&'s str> {
+    slots
+        .iter()
+        .enumerate()
+        .filter(|(offset, slot)| slot.required && !(*offset == 0 && seen_first > 0))
+        .map(|(_, slot)| slot.name)
+        .collect()
+}